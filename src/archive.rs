@@ -0,0 +1,217 @@
+// 压缩包（ZIP、7z、tar、tar.gz）：在内存中枚举成员，不解压到磁盘
+use anyhow::{Context, Result};
+use colored::Colorize;
+use flate2::read::GzDecoder;
+use std::io::{Cursor, Read};
+
+// 单个成员与全部成员合计的大小上限，防止压缩炸弹耗尽内存
+const MAX_MEMBER: u64 = 256 << 20;
+const MAX_TOTAL: u64 = 1 << 30;
+
+// 剩余的读取额度
+struct Budget {
+	member: u64,
+	left: u64,
+}
+
+pub fn is_archive(buf: &[u8]) -> bool {
+	buf.starts_with(b"PK\x03\x04") || buf.starts_with(b"PK\x05\x06") || is_7z(buf) || is_gzip(buf) || is_tar(buf)
+}
+
+fn is_7z(buf: &[u8]) -> bool {
+	buf.starts_with(&[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C])
+}
+
+fn is_gzip(buf: &[u8]) -> bool {
+	buf.starts_with(&[0x1F, 0x8B])
+}
+
+// POSIX/GNU tar 头部第 257 字节起为 "ustar"
+fn is_tar(buf: &[u8]) -> bool {
+	buf.get(257..262) == Some(b"ustar")
+}
+
+// 超过单个成员上限的成员跳过；合计超过上限后其余成员都不再读取。跳过时返回 None
+fn read_member(r: &mut dyn Read, name: &str, budget: &mut Budget) -> Result<Option<Vec<u8>>> {
+	if budget.left == 0 {
+		return Ok(None);
+	}
+	let mut data = Vec::new();
+	r.take(budget.member.min(budget.left) + 1).read_to_end(&mut data).with_context(|| format!("read {}", name))?;
+	let len = data.len() as u64;
+	if len > budget.member {
+		eprintln!("{} {}: member larger than {} MiB, skipped", "[!]".red(), name, budget.member >> 20);
+		return Ok(None);
+	}
+	if len > budget.left {
+		eprintln!("{} {}: archive members exceed {} MiB in total, skipping the rest", "[!]".red(), name, MAX_TOTAL >> 20);
+		budget.left = 0;
+		return Ok(None);
+	}
+	budget.left -= len;
+	Ok(Some(data))
+}
+
+// 返回 (成员路径, 内容)，按压缩包内的顺序，目录跳过；非 tar 的 gzip 视为单个成员 "gzip"
+pub fn members(buf: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+	members_within(buf, &mut Budget { member: MAX_MEMBER, left: MAX_TOTAL })
+}
+
+fn members_within(buf: &[u8], budget: &mut Budget) -> Result<Vec<(String, Vec<u8>)>> {
+	if is_7z(buf) {
+		return members_7z(buf, budget);
+	}
+	if is_gzip(buf) {
+		// tar.gz 直接从解压流中读取成员，不整体解压到内存
+		let mut dec = GzDecoder::new(buf);
+		let mut head = Vec::new();
+		(&mut dec).take(512).read_to_end(&mut head).context("read gzip")?;
+		let tar = is_tar(&head);
+		let mut r = Cursor::new(head).chain(dec);
+		if tar {
+			return members_tar(r, budget);
+		}
+		return Ok(read_member(&mut r, "gzip", budget)?.map(|data| vec![("gzip".to_string(), data)]).unwrap_or_default());
+	}
+	if is_tar(buf) {
+		return members_tar(buf, budget);
+	}
+	members_zip(buf, budget)
+}
+
+fn members_zip(buf: &[u8], budget: &mut Budget) -> Result<Vec<(String, Vec<u8>)>> {
+	let mut zip = zip::ZipArchive::new(Cursor::new(buf)).context("open zip")?;
+	let mut out = Vec::new();
+	for i in 0..zip.len() {
+		let mut f = zip.by_index(i).with_context(|| format!("zip entry {}", i))?;
+		if f.is_dir() {
+			continue;
+		}
+		let name = f.name().to_string();
+		if let Some(data) = read_member(&mut f, &name, budget)? {
+			out.push((name, data));
+		}
+	}
+	Ok(out)
+}
+
+fn members_tar(r: impl Read, budget: &mut Budget) -> Result<Vec<(String, Vec<u8>)>> {
+	let mut tar = tar::Archive::new(r);
+	let mut out = Vec::new();
+	for entry in tar.entries().context("open tar")? {
+		let mut entry = entry.context("tar entry")?;
+		if !entry.header().entry_type().is_file() {
+			continue;
+		}
+		let name = entry.path().context("tar entry path")?.to_string_lossy().to_string();
+		if let Some(data) = read_member(&mut entry, &name, budget)? {
+			out.push((name, data));
+		}
+	}
+	Ok(out)
+}
+
+fn members_7z(buf: &[u8], budget: &mut Budget) -> Result<Vec<(String, Vec<u8>)>> {
+	let mut sz = sevenz_rust::SevenZReader::new(Cursor::new(buf), buf.len() as u64, sevenz_rust::Password::empty())
+		.context("open 7z")?;
+	let mut out = Vec::new();
+	let mut err = None;
+	sz.for_each_entries(|entry, r| {
+		if entry.is_directory() || !entry.has_stream() {
+			return Ok(true);
+		}
+		match read_member(r, entry.name(), budget) {
+			Ok(Some(data)) => out.push((entry.name().to_string(), data)),
+			// 额度用完：其余成员不再解压
+			Ok(None) if budget.left == 0 => return Ok(false),
+			// 固实压缩的成员共用一个解压流，跳过的成员也要读完
+			Ok(None) => {
+				std::io::copy(r, &mut std::io::sink())?;
+			}
+			Err(e) => {
+				err = Some(e);
+				return Ok(false);
+			}
+		}
+		Ok(true)
+	})
+	.context("read 7z")?;
+	match err {
+		Some(e) => Err(e),
+		None => Ok(out),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+
+	fn names(members: &[(String, Vec<u8>)]) -> Vec<(&str, usize)> {
+		members.iter().map(|(n, d)| (n.as_str(), d.len())).collect()
+	}
+
+	fn zip(files: &[(&str, usize)]) -> Vec<u8> {
+		let mut w = zip::ZipWriter::new(Cursor::new(Vec::new()));
+		w.add_directory("dir/", zip::write::SimpleFileOptions::default()).unwrap();
+		for &(name, len) in files {
+			w.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+			w.write_all(&vec![b'a'; len]).unwrap();
+		}
+		w.finish().unwrap().into_inner()
+	}
+
+	fn tar_gz(files: &[(&str, usize)]) -> Vec<u8> {
+		let mut b = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+		for &(name, len) in files {
+			let mut h = tar::Header::new_ustar();
+			h.set_size(len as u64);
+			h.set_mode(0o644);
+			h.set_cksum();
+			b.append_data(&mut h, name, &vec![b'a'; len][..]).unwrap();
+		}
+		b.into_inner().unwrap().finish().unwrap()
+	}
+
+	#[test]
+	fn zip_members() {
+		let buf = zip(&[("a.png", 10), ("dir/b.bmp", 20)]);
+		assert!(is_archive(&buf));
+		assert_eq!(names(&members(&buf).unwrap()), [("a.png", 10), ("dir/b.bmp", 20)]);
+	}
+
+	#[test]
+	fn tar_gz_members() {
+		let buf = tar_gz(&[("a.png", 10), ("dir/b.bmp", 700)]);
+		assert!(is_archive(&buf));
+		assert_eq!(names(&members(&buf).unwrap()), [("a.png", 10), ("dir/b.bmp", 700)]);
+
+		// 不是 tar 的 gzip 作为单个成员
+		let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+		gz.write_all(&[b'a'; 1000]).unwrap();
+		assert_eq!(names(&members(&gz.finish().unwrap()).unwrap()), [("gzip", 1000)]);
+	}
+
+	#[test]
+	fn seven_zip_members() {
+		let buf = std::fs::read(format!("{}/samples/EasyBMP.bmp.7z", env!("CARGO_MANIFEST_DIR"))).unwrap();
+		assert!(is_archive(&buf));
+		let members = members(&buf).unwrap();
+		assert_eq!(members.len(), 1);
+		assert_eq!(members[0].0, "EasyBMP.bmp");
+		assert!(members[0].1.starts_with(b"BM"));
+	}
+
+	#[test]
+	fn oversized_members_are_skipped() {
+		// 超过单个成员上限的跳过，其余照常读取
+		for buf in [zip(&[("big.png", 100), ("a.png", 10)]), tar_gz(&[("big.png", 100), ("a.png", 10)])] {
+			let m = members_within(&buf, &mut Budget { member: 50, left: 1000 }).unwrap();
+			assert_eq!(names(&m), [("a.png", 10)]);
+		}
+		// 合计超过上限后其余成员都不再读取
+		let buf = zip(&[("a.png", 40), ("b.png", 40), ("c.png", 10)]);
+		let m = members_within(&buf, &mut Budget { member: 50, left: 60 }).unwrap();
+		assert_eq!(names(&m), [("a.png", 40)]);
+	}
+}
@@ -0,0 +1,203 @@
+// 未压缩 PCM 音频（WAV、AU）：声道数、样本位宽、原始样本数据，以及 LIST/INFO、注释等其余字段
+use anyhow::{bail, Context, Result};
+
+#[derive(Debug, Clone)]
+pub struct Audio {
+	pub channels: usize,
+	pub sample_bits: usize,
+	pub big_endian: bool,
+	pub data: Vec<u8>, // 原始 PCM 数据，按帧交织
+	pub fields: Vec<(String, Vec<u8>)>,
+	pub trailer: Vec<u8>, // RIFF 声明大小或 AU 数据大小之后的数据
+}
+
+impl Audio {
+	pub fn frames(&self) -> usize {
+		self.data.len() / (self.channels * self.sample_bits / 8)
+	}
+
+	// 每个样本的最低字节（LSB 所在），按帧交织，只保留完整的帧
+	pub fn low_bytes(&self) -> Vec<u8> {
+		let size = self.sample_bits / 8;
+		let end = self.frames() * self.channels * size;
+		self.data[..end].chunks_exact(size).map(|s| if self.big_endian { s[size - 1] } else { s[0] }).collect()
+	}
+
+	// 声道名：立体声为 l、r，任意声道数都可用 c0、c1…
+	pub fn channel_index(&self, name: &str) -> Option<usize> {
+		let idx = match name {
+			"l" if self.channels == 2 => 0,
+			"r" if self.channels == 2 => 1,
+			_ => name.strip_prefix('c')?.parse().ok()?,
+		};
+		(idx < self.channels).then_some(idx)
+	}
+
+	pub fn channel_names(&self) -> Vec<String> {
+		if self.channels == 2 {
+			vec!["l".into(), "r".into()]
+		} else {
+			(0..self.channels).map(|i| format!("c{}", i)).collect()
+		}
+	}
+}
+
+fn check_format(channels: usize, sample_bits: usize) -> Result<()> {
+	if channels == 0 || channels > 64 {
+		bail!("bad channel count {}", channels);
+	}
+	if !matches!(sample_bits, 8 | 16 | 24 | 32) {
+		bail!("unsupported sample size {} bits", sample_bits);
+	}
+	Ok(())
+}
+
+pub fn parse_wav(data: &[u8]) -> Result<Audio> {
+	if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+		bail!("Not a WAV file");
+	}
+	let end = (u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize)
+		.saturating_add(8)
+		.min(data.len());
+	let mut fmt = None;
+	let mut pcm = None;
+	let mut fields = Vec::new();
+	let mut pos = 12;
+	while pos + 8 <= end {
+		let ty = &data[pos..pos + 4];
+		let size = u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]]) as usize;
+		let body = &data[pos + 8..(pos + 8).saturating_add(size).min(end)];
+		match ty {
+			b"fmt " => fmt = Some(body),
+			b"data" => pcm = Some(body),
+			b"fact" => {}
+			// LIST/INFO：IART、ICMT 等文本，去掉末尾的 NUL
+			b"LIST" if body.starts_with(b"INFO") => {
+				let mut p = 4;
+				while p + 8 <= body.len() {
+					let id = String::from_utf8_lossy(&body[p..p + 4]).to_string();
+					let n = u32::from_le_bytes([body[p + 4], body[p + 5], body[p + 6], body[p + 7]]) as usize;
+					let v = &body[p + 8..(p + 8).saturating_add(n).min(body.len())];
+					let v = v.iter().rposition(|&b| b != 0).map(|i| &v[..=i]).unwrap_or_default();
+					fields.push((format!("INFO {}", id), v.to_vec()));
+					p = p + 8 + n + n % 2;
+				}
+			}
+			_ => fields.push((format!("chunk {}", String::from_utf8_lossy(ty)), body.to_vec())),
+		}
+		// 块按偶数字节对齐
+		pos = pos + 8 + size + size % 2;
+	}
+
+	let fmt = fmt.filter(|f| f.len() >= 16).context("missing WAV fmt chunk")?;
+	let rd16 = |p: usize| u16::from_le_bytes([fmt[p], fmt[p + 1]]) as usize;
+	// 1 = PCM，3 = IEEE float，0xFFFE = WAVE_FORMAT_EXTENSIBLE（子格式 GUID 的前两个字节）
+	let tag = if rd16(0) == 0xFFFE && fmt.len() >= 26 { rd16(24) } else { rd16(0) };
+	if !matches!(tag, 1 | 3) {
+		bail!("unsupported WAV format 0x{:04X} (only PCM)", tag);
+	}
+	let (channels, sample_bits) = (rd16(2), rd16(14));
+	check_format(channels, sample_bits)?;
+	Ok(Audio {
+		channels,
+		sample_bits,
+		big_endian: false,
+		data: pcm.context("missing WAV data chunk")?.to_vec(),
+		fields,
+		trailer: data[end..].to_vec(),
+	})
+}
+
+pub fn parse_au(data: &[u8]) -> Result<Audio> {
+	if data.len() < 24 || &data[0..4] != b".snd" {
+		bail!("Not an AU file");
+	}
+	let rd32 = |p: usize| u32::from_be_bytes([data[p], data[p + 1], data[p + 2], data[p + 3]]) as usize;
+	let (offset, size, encoding, channels) = (rd32(4), rd32(8), rd32(12), rd32(20));
+	// 2..=5：8/16/24/32 位有符号线性 PCM
+	let sample_bits = match encoding {
+		2..=5 => (encoding - 1) * 8,
+		_ => bail!("unsupported AU encoding {} (only linear PCM)", encoding),
+	};
+	check_format(channels, sample_bits)?;
+	let start = offset.clamp(24, data.len());
+	// 大小为 0xFFFFFFFF 表示未知，数据直到文件末尾
+	let end = if size == 0xFFFF_FFFF { data.len() } else { start.saturating_add(size).min(data.len()) };
+	let mut fields = Vec::new();
+	let note = &data[24..start];
+	let note = note.iter().rposition(|&b| b != 0).map(|i| &note[..=i]).unwrap_or_default();
+	if !note.is_empty() {
+		fields.push(("annotation".to_string(), note.to_vec()));
+	}
+	Ok(Audio { channels, sample_bits, big_endian: true, data: data[start..end].to_vec(), fields, trailer: data[end..].to_vec() })
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::imageio::Image;
+	use crate::opts::{Options, OrderSpec};
+
+	// PCM WAV：每个样本的最低位依次取 text 的比特（高位在前），其余位为非零噪声
+	fn wav(channels: usize, sample_bits: usize, text: &[u8]) -> Vec<u8> {
+		let size = sample_bits / 8;
+		let mut pcm = Vec::new();
+		for (i, bit) in text.iter().flat_map(|&c| (0..8).rev().map(move |k| (c >> k) & 1)).enumerate() {
+			let mut s = vec![0x5A; size];
+			s[0] = (i as u8).wrapping_mul(37) & 0xFE | bit;
+			pcm.extend(s);
+		}
+		// 补齐最后一帧
+		pcm.resize(pcm.len().next_multiple_of(channels * size), 0);
+		let mut fmt = Vec::new();
+		fmt.extend_from_slice(&1u16.to_le_bytes());
+		fmt.extend_from_slice(&(channels as u16).to_le_bytes());
+		fmt.extend_from_slice(&8000u32.to_le_bytes());
+		fmt.extend_from_slice(&((8000 * channels * size) as u32).to_le_bytes());
+		fmt.extend_from_slice(&((channels * size) as u16).to_le_bytes());
+		fmt.extend_from_slice(&(sample_bits as u16).to_le_bytes());
+		let mut out = b"RIFF\0\0\0\0WAVE".to_vec();
+		for (ty, body) in [(b"fmt ", &fmt), (b"data", &pcm)] {
+			out.extend_from_slice(ty);
+			out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+			out.extend_from_slice(body);
+		}
+		let riff = out.len() as u32 - 8;
+		out[4..8].copy_from_slice(&riff.to_le_bytes());
+		out
+	}
+
+	fn extract(data: &[u8], channels: &str, limit: usize) -> Vec<u8> {
+		let img = Image::from_bytes(data).unwrap();
+		let opts = Options {
+			channels: Some(vec![channels.to_string()]),
+			bits: Some(vec![1]),
+			order: OrderSpec::Explicit(vec!["xy".to_string()]),
+			limit,
+			..Default::default()
+		};
+		crate::extractor::extract(&img, &opts, &mut String::new())
+	}
+
+	#[test]
+	fn stereo_16bit_lsb_text() {
+		let text = b"flag{stereo lsb}";
+		let data = wav(2, 16, text);
+		let img = Image::from_bytes(&data).unwrap();
+		let audio = img.audio.as_ref().unwrap();
+		assert_eq!((audio.channels, audio.sample_bits, audio.frames()), (2, 16, text.len() * 4));
+		assert_eq!(extract(&data, "lr", text.len()), text);
+		// 单个声道只取到隔位的比特
+		assert_ne!(extract(&data, "l", text.len() / 2), &text[..text.len() / 2]);
+	}
+
+	#[test]
+	fn mono_24bit_lsb_text() {
+		let text = b"flag{24-bit mono}";
+		let data = wav(1, 24, text);
+		let img = Image::from_bytes(&data).unwrap();
+		let audio = img.audio.as_ref().unwrap();
+		assert_eq!((audio.channels, audio.sample_bits, audio.frames()), (1, 24, text.len() * 8));
+		assert_eq!(audio.channel_names(), ["c0"]);
+		assert_eq!(extract(&data, "c0", text.len()), text);
+	}
+}
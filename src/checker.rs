@@ -1,8 +1,8 @@
 use std::collections::{HashMap, HashSet};
 use colored::Colorize;
 use crate::imageio::Image;
-use crate::opts::{Options, OrderSpec, BitOrder};
-use crate::result::DetectResult;
+use crate::opts::{Options, OrderSpec, BitOrder, ByteSource};
+use crate::result::{DetectResult, Finding};
 use crate::hexdump;
 use crate::file_cmd::FileCmd;
 use flate2::read::ZlibDecoder;
@@ -19,12 +19,13 @@ struct CheckState {
 pub struct Checker<'a> {
 	pub image: &'a Image,
 	pub options: &'a Options,
+	pub prefix: String, // 标题前缀，如子图像的 "frame:1:"
 }
 
 impl<'a> Checker<'a> {
-	pub fn new(image: &'a Image, options: &'a Options) -> Self { Self { image, options } }
+	pub fn new(image: &'a Image, options: &'a Options) -> Self { Self { image, options, prefix: String::new() } }
 
-	pub fn check(&self) -> Vec<DetectResult> {
+	pub fn check(&self) -> Vec<Finding> {
 		let mut results = Vec::new();
 		let mut state = CheckState {
 			cache: HashMap::new(),
@@ -34,53 +35,160 @@ impl<'a> Checker<'a> {
 			file_cmd: FileCmd::new(self.options.file_cmd),
 		};
 
-		// metadata
-		for (k, v) in &self.image.metadata {
-			let _r = DetectResult::WholeText(v.clone());
-			if self.process_result(&v.as_bytes().to_vec(), &format!("meta {}", k), true, &mut state, &mut results) {
+		self.check_image(&mut state, &mut results);
+
+		if state.found_anything {
+			if state.need_cr { print!("\r{}\r", " ".repeat(20)); }
+		} else {
+			println!("\r[=] nothing :({}", " ".repeat(20));
+		}
+
+		results
+	}
+
+	fn check_image(&self, state: &mut CheckState, results: &mut Vec<Finding>) {
+		// 加载时跳过的结构（无法解码的 TIFF 页等）
+		if self.options.verbose >= 0 {
+			for w in &self.image.warnings {
+				println!("{} {}", "[?]".yellow(), w);
+			}
+		}
+
+		// metadata：标题包含来源块索引与语言标签，如 "meta:3:Comment[en]"
+		for m in &self.image.metadata {
+			let title = format!(
+				"meta{}{}{}",
+				m.chunk.map(|i| format!(":{}:", i)).unwrap_or_else(|| " ".to_string()),
+				m.key,
+				m.lang.as_ref().map(|l| format!("[{}]", l)).unwrap_or_default()
+			);
+			if self.process_result(&m.value, &title, true, state, results) {
+				state.found_anything = true;
+			}
+			if let Some(t) = &m.translated
+				&& self.process_result(t, &format!("{} translated", title), true, state, results) {
 				state.found_anything = true;
 			}
 		}
 
-		// imagedata - 对于特殊检查，使用 limit=0 表示搜索整个数据
+		// imagedata - 对于特殊检查，使用 limit=0 表示搜索整个数据；
+		// Adam7 pass 的扫描线已包含在主图像的 imagedata 中，不再重复
+		let is_pass = self.image.adam7_pass;
 		let mut temp_opts = self.options.clone();
 		temp_opts.limit = 0; // 搜索整个 imagedata
-		let temp_checker = Checker { image: self.image, options: &temp_opts };
-		let result_imagedata = temp_checker.data2result(&self.image.imagedata, &mut state);
-		if let Some(r) = result_imagedata {
-			if !matches!(r, DetectResult::OneChar { .. }) {
-				self.show_title("imagedata", true);
-				println!("{}", r);
-				results.push(r);
-				state.found_anything = true;
-			}
+		let temp_checker = Checker { image: self.image, options: &temp_opts, prefix: self.prefix.clone() };
+		let result_imagedata = if is_pass { None } else { temp_checker.data2result(&self.image.imagedata, state) };
+		if let Some(r) = result_imagedata
+			&& !matches!(r, DetectResult::OneChar { .. }) {
+			self.show_title("imagedata", true);
+			println!("{}", r);
+			results.push(self.finding("imagedata", r));
+			state.found_anything = true;
 		}
 
+		// steganography-png 工具的尾部（位于去除 filter 后的原始扫描线数据末尾）
+		if let Some(r) = Self::check_steganography_png(self.image) {
+			self.show_title("scanlines", true);
+			println!("{}", r);
+			results.push(self.finding("scanlines", r));
+			state.found_anything = true;
+		}
+
+		// scanline：每行 filter 类型字节
+		self.check_scanline(state, results);
+
 		// extradata - IEND后的额外数据
-		if !self.image.extradata.is_empty() {
-			for (idx, extra) in self.image.extradata.iter().enumerate() {
-				if !extra.is_empty() {
-					// 显示提示信息
-					if self.options.verbose >= 0 {
-						println!("{} {} bytes of extra data after image end (IEND)", 
-							"[?]".yellow(), extra.len());
-					}
-					
-					let title = format!("extradata:{}", idx);
+		for (idx, extra) in self.image.extradata.iter().enumerate() {
+			let notice = match self.image.format {
+				crate::imageio::ImgFormat::Gif => format!("{} bytes of extra data after image end (trailer)", extra.len()),
+				crate::imageio::ImgFormat::Pnm => format!("{} bytes of extra data after image end (raster)", extra.len()),
+				crate::imageio::ImgFormat::WebP => format!("{} bytes of extra data after image end (RIFF)", extra.len()),
+				crate::imageio::ImgFormat::Wav => format!("{} bytes of extra data after audio end (RIFF)", extra.len()),
+				crate::imageio::ImgFormat::Au => format!("{} bytes of extra data after audio end (AU data size)", extra.len()),
+				crate::imageio::ImgFormat::Jpeg => format!("{} bytes of extra data after image end (EOI)", extra.len()),
+				crate::imageio::ImgFormat::Qoi => format!("{} bytes of extra data after image end (QOI end marker)", extra.len()),
+				crate::imageio::ImgFormat::Tga => format!("{} bytes of data not referenced by TGA header or footer", extra.len()),
+				// TIFF：条带之间或最后一个 IFD 之后未被引用的数据
+				crate::imageio::ImgFormat::Tiff => format!("{} bytes of data not referenced by any IFD", extra.len()),
+				_ => format!("{} bytes of extra data after image end (IEND)", extra.len()),
+			};
+			self.check_hidden_data(&format!("extradata:{}", idx), &notice, extra, state, results);
+		}
+
+		// IDAT 内 zlib 流结束后的数据，以及解压后超出图像大小的扫描线数据
+		let trailer = self.image.idat_trailer();
+		let notice = format!("{} bytes of data after zlib stream end in IDAT", trailer.len());
+		self.check_hidden_data("idat:trailer", &notice, &trailer, state, results);
+		let excess = self.image.imagedata_excess();
+		let notice = format!("{} bytes of decompressed IDAT data beyond image size", excess.len());
+		self.check_hidden_data("imagedata:excess", &notice, excess, state, results);
+
+		// IHDR CRC 对应的真实宽高
+		if let Some((w, h)) = self.image.recovered_size {
+			let r = DetectResult::ChunkAnomaly(format!("IHDR CRC matches size {}x{}", w, h));
+			self.show_title("chunk:0:IHDR", true);
+			println!("{}", r);
+			results.push(self.finding("chunk:0:IHDR", r));
+			state.found_anything = true;
+			if (w, h) != (self.image.width, self.image.height) && self.options.verbose >= 0 {
+				println!("{} rerun with --fix-ihdr to scan the full {}x{} image", "[?]".yellow(), w, h);
+			}
+		}
+
+		// PNG 块结构异常
+		for (idx, desc) in crate::imageio::chunk_anomalies(&self.image.chunks) {
+			let ch = &self.image.chunks[idx];
+			let r = DetectResult::ChunkAnomaly(desc);
+			let title = format!("chunk:{}:{}", idx, String::from_utf8_lossy(&ch.ty));
+			self.show_title(&title, true);
+			println!("{}", r);
+			results.push(self.finding(&title, r));
+			state.found_anything = true;
+		}
+
+		// JPEG 标记段：-v 时列出全部标记，结构异常作为发现
+		if let Some(jpeg) = &self.image.jpeg {
+			if self.options.verbose > 0 {
+				let names: Vec<String> = jpeg.segments.iter().map(|s| crate::jpeg::marker_name(s.marker)).collect();
+				println!("{} markers: {}", "[?]".yellow(), names.join(" "));
+			}
+			for (idx, desc) in jpeg.anomalies() {
+				let r = DetectResult::ChunkAnomaly(desc);
+				let title = format!("segment:{}:{}", idx, crate::jpeg::marker_name(jpeg.segments[idx].marker));
+				self.show_title(&title, true);
+				println!("{}", r);
+				results.push(self.finding(&title, r));
+				state.found_anything = true;
+			}
+			// JSteg：非 0/1 系数的 LSB 开头为长度头
+			if !jpeg.coefficients.is_empty() {
+				let mut local = self.options.clone();
+				local.order = OrderSpec::Explicit(vec!["by".into()]);
+				local.bits = Some(vec![1]);
+				local.bit_order = Some(BitOrder::Lsb);
+				local.byte_source = Some(ByteSource::Dct);
+				local.limit = 0;
+				let lsb = crate::extractor::extract(self.image, &local, &mut String::new());
+				if let Some((size, data)) = crate::jpeg::jsteg_payload(&lsb)
+					&& self.data2result(&data, state).is_some_and(|r| !matches!(r, DetectResult::OneChar { .. })) {
+					let r = DetectResult::LsbTool { tool: "JSteg".to_string(), size: Some(size), data };
+					self.show_title("dct:jsteg", true);
+					println!("{}", r);
+					results.push(self.finding("dct:jsteg", r));
+					state.found_anything = true;
+				}
+			}
+			// F5 / OutGuess / steghide：只能给出统计上的可能性
+			for score in crate::steganalysis::analyze(jpeg) {
+				if self.options.verbose > 0 {
+					println!("{} {} confidence {:.2} ({})", "[?]".yellow(), score.tool, score.confidence, score.detail);
+				}
+				if score.confidence >= 0.5 {
+					let r = DetectResult::Steganalysis { tool: score.tool.to_string(), confidence: score.confidence, detail: score.detail };
+					let title = format!("dct:{}", score.tool);
 					self.show_title(&title, true);
-					
-					// 显示hexdump（始终显示，因为这是特殊数据）
-					println!("\n{}", hexdump::dump(4, extra, 0));
-					
-					// 也尝试分析内容
-					let temp_checker = Checker { image: self.image, options: &temp_opts };
-					if let Some(r) = temp_checker.data2result(extra, &mut state) {
-						if !matches!(r, DetectResult::OneChar { .. }) {
-							println!("{}", r);
-							results.push(r);
-						}
-					}
-					
+					println!("{}", r);
+					results.push(self.finding(&title, r));
 					state.found_anything = true;
 				}
 			}
@@ -90,16 +198,23 @@ impl<'a> Checker<'a> {
 		for (idx, ch) in self.image.chunks.iter().enumerate() {
 			if ch.data.len() >= 5 && &ch.ty != b"IDAT" {
 				let title = format!("chunk:{}:{}", idx, std::str::from_utf8(&ch.ty).unwrap_or("????"));
-				state.need_cr = !self.process_result(&ch.data, &title, true, &mut state, &mut results);
+				state.need_cr = !self.process_result(&ch.data, &title, true, state, results);
 				state.found_anything |= !state.need_cr;
 			}
 		}
 
-		// 主扫描：按 Ruby check_channels 流程
+		// 主扫描：按 Ruby check_channels 流程；JPEG 只扫描 DCT 系数
 		let orders: Vec<String> = match &self.options.order {
+			_ if self.image.pixels.is_empty() && self.image.jpeg.as_ref().is_none_or(|j| j.coefficients.is_empty()) => Vec::new(),
+			// JPEG：按字节遍历 DCT 系数
+			OrderSpec::All | OrderSpec::Auto if self.image.pixels.is_empty() => vec!["by".into()],
+			// Adam7 pass 的像素已在主图像中按 xy 扫描过，逐个 pass 扫描只在 -a 或显式 -o 时进行
+			OrderSpec::Auto if self.image.adam7_pass => Vec::new(),
 			OrderSpec::All => {
 				if self.image.format == crate::imageio::ImgFormat::Bmp {
 					vec!["bY".into(), "xY".into(), "xy".into(), "yx".into(), "XY".into(), "YX".into(), "Xy".into(), "yX".into(), "Yx".into()]
+				} else if self.image.format == crate::imageio::ImgFormat::Gif {
+					vec!["xy".into(), "yx".into(), "XY".into(), "YX".into(), "Xy".into(), "yX".into(), "xY".into(), "Yx".into(), "by".into()]
 				} else {
 					vec!["xy".into(), "yx".into(), "XY".into(), "YX".into(), "Xy".into(), "yX".into(), "xY".into(), "Yx".into()]
 				}
@@ -107,11 +222,15 @@ impl<'a> Checker<'a> {
 			OrderSpec::Auto => {
 				if self.image.format == crate::imageio::ImgFormat::Bmp {
 					vec!["bY".into(), "xY".into()]
+				} else if self.image.format == crate::imageio::ImgFormat::Gif {
+					// GIF 的 imagedata 为调色板索引，按字节遍历即索引 LSB
+					vec!["xy".into(), "by".into()]
 				} else {
 					vec!["xy".into()]
 				}
 			},
 			OrderSpec::Explicit(v) => v.clone(),
+			OrderSpec::Random { .. } => vec![self.options.order.to_string()],
 		};
 		let bits_list: Vec<u16> = self.options.bits.clone().unwrap_or_else(|| vec![1,2,3,4]);
 
@@ -123,53 +242,62 @@ impl<'a> Checker<'a> {
 			};
 			for &prime in &prime_vals {
 				for &bits in &bits_list {
-					if order.contains('b') || order.contains('B') {
+					if !order.starts_with("rand:") && (order.contains('b') || order.contains('B')) {
 						// byte iterator: no channels
-						self.check_one_combination(&order, prime, bits, &mut state, &mut results);
+						self.check_one_combination(&order, prime, bits, state, results);
 					} else {
 						// pixel iterator: iterate channels
 						let channels = if let Some(ref chs) = self.options.channels {
 							chs.clone()
 						} else {
-							default_channels()
+							default_channels(self.image)
 						};
 						for ch in &channels {
-							self.check_one_combination_with_channel(&order, prime, bits, ch, &mut state, &mut results);
+							self.check_one_combination_with_channel(&order, prime, bits, ch, state, results);
 						}
 					}
 				}
 			}
 		}
 
-		if state.found_anything {
-			if state.need_cr { print!("\r{}\r", " ".repeat(20)); }
-		} else {
-			println!("\r[=] nothing :({}", " ".repeat(20));
+		// 子图像（APNG 帧、帧差等）：共用缓存，标题加前缀
+		for (label, sub) in &self.image.subimages {
+			let checker = Checker { image: sub, options: self.options, prefix: format!("{}{}:", self.prefix, label) };
+			checker.check_image(state, results);
 		}
-
-		results
 	}
 
-	fn check_one_combination(&self, order: &str, prime: bool, bits: u16, state: &mut CheckState, results: &mut Vec<DetectResult>) {
+	fn check_one_combination(&self, order: &str, prime: bool, bits: u16, state: &mut CheckState, results: &mut Vec<Finding>) {
 		// byte iterator: 处理 bY, bX 等顺序
-		let bit_orders: Vec<BitOrder> = if self.options.bit_order.is_none() {
-			vec![BitOrder::Lsb, BitOrder::Msb]
+		let bit_orders: Vec<BitOrder> = match self.options.bit_order {
+			Some(bo) => vec![bo],
+			None => vec![BitOrder::Lsb, BitOrder::Msb],
+		};
+
+		// PNG 的 imagedata 带 filter 字节，另外扫描去除 filter 后的 rawpixels；标题中注明来源
+		let sources: Vec<Option<ByteSource>> = if self.image.format == crate::imageio::ImgFormat::Png {
+			match self.options.byte_source {
+				Some(src) => vec![Some(src)],
+				None => vec![Some(ByteSource::ImageData), Some(ByteSource::RawPixels)],
+			}
+		} else if self.image.format == crate::imageio::ImgFormat::Jpeg {
+			vec![Some(self.options.byte_source.unwrap_or(ByteSource::Dct))]
 		} else {
-			vec![self.options.bit_order.unwrap()]
+			vec![None]
 		};
 
-		for bo in bit_orders {
+		for (bo, src) in bit_orders.into_iter().flat_map(|bo| sources.iter().map(move |&src| (bo, src))) {
 			let title = format!(
-				"b{},{},{}{}",
+				"b{},{},{}{}{}",
 				bits,
 				match bo { BitOrder::Lsb => "lsb", BitOrder::Msb => "msb" },
 				order,
-				if prime { ",prime" } else { "" }
+				if prime { ",prime" } else { "" },
+				src.map(|s| format!(",{}", s.name())).unwrap_or_default()
 			);
 
 			// 标题去重
-			if state.wastitles.contains(&title) { continue; }
-			state.wastitles.insert(title.clone());
+			if !state.wastitles.insert(format!("{}{}", self.prefix, title)) { continue; }
 
 			let mut local = self.options.clone();
 			local.order = OrderSpec::Explicit(vec![order.to_string()]);
@@ -177,6 +305,7 @@ impl<'a> Checker<'a> {
 			local.bits = Some(vec![bits]);
 			local.bit_order = Some(bo);
 			local.channels = None; // byte iterator 不使用 channels
+			local.byte_source = src;
 
 			let mut _title_out = String::new();
 			let data = crate::extractor::extract(self.image, &local, &mut _title_out);
@@ -186,12 +315,11 @@ impl<'a> Checker<'a> {
 		}
 	}
 
-	fn check_one_combination_with_channel(&self, order: &str, prime: bool, bits: u16, ch: &str, state: &mut CheckState, results: &mut Vec<DetectResult>) {
+	fn check_one_combination_with_channel(&self, order: &str, prime: bool, bits: u16, ch: &str, state: &mut CheckState, results: &mut Vec<Finding>) {
 		// 若未指定 bit_order，则遍历 lsb 和 msb
-		let bit_orders: Vec<BitOrder> = if self.options.bit_order.is_none() {
-			vec![BitOrder::Lsb, BitOrder::Msb]
-		} else {
-			vec![self.options.bit_order.unwrap()]
+		let bit_orders: Vec<BitOrder> = match self.options.bit_order {
+			Some(bo) => vec![bo],
+			None => vec![BitOrder::Lsb, BitOrder::Msb],
 		};
 
 		for bo in bit_orders {
@@ -205,11 +333,10 @@ impl<'a> Checker<'a> {
 			);
 
 			// 标题去重
-			if state.wastitles.contains(&title) { continue; }
-			state.wastitles.insert(title.clone());
+			if !state.wastitles.insert(format!("{}{}", self.prefix, title)) { continue; }
 
 			let mut local = self.options.clone();
-			local.order = OrderSpec::Explicit(vec![order.to_string()]);
+			local.order = crate::opts::parse_order(order).unwrap_or_else(|_| OrderSpec::Explicit(vec![order.to_string()]));
 			local.prime = if prime { crate::opts::PrimeSpec::Only } else { crate::opts::PrimeSpec::None };
 			local.bits = Some(vec![bits]);
 			local.bit_order = Some(bo);
@@ -223,7 +350,7 @@ impl<'a> Checker<'a> {
 		}
 	}
 
-	fn process_result(&self, data: &[u8], title: &str, show_title_always: bool, state: &mut CheckState, results: &mut Vec<DetectResult>) -> bool {
+	fn process_result(&self, data: &[u8], title: &str, show_title_always: bool, state: &mut CheckState, results: &mut Vec<Finding>) -> bool {
 		// 缓存检查：完整数据去重
 		if let Some(cached_title) = state.cache.get(data) {
 			if self.options.verbose > 1 {
@@ -241,13 +368,13 @@ impl<'a> Checker<'a> {
 
 		// verbosity <= 0: 仅当找到非 OneChar 结果时输出
 		if self.options.verbose <= 0 {
-			if let Some(ref r) = result {
-				if !matches!(r, DetectResult::OneChar { .. }) {
-					self.show_title(title, true);
-					println!("{}", r);
-					results.push(r.clone());
-					return true;
-				}
+			if let Some(ref r) = result
+				&& !matches!(r, DetectResult::OneChar { .. }) {
+				self.show_title(title, true);
+				println!("{}", r);
+				results.push(self.finding(title, r.clone()));
+				self.try_crack(r, data, results);
+				return true;
 			}
 			return false;
 		}
@@ -265,7 +392,8 @@ impl<'a> Checker<'a> {
 				let dump = hexdump::dump(4, data, self.options.limit);
 				print!("{}", dump);
 			}
-			results.push(r);
+			self.try_crack(&r, data, results);
+			results.push(self.finding(title, r));
 			return true;
 		}
 
@@ -279,7 +407,37 @@ impl<'a> Checker<'a> {
 		false
 	}
 
+	// 对加密载荷进行字典攻击，成功时输出口令与解密数据
+	fn try_crack(&self, r: &DetectResult, data: &[u8], results: &mut Vec<Finding>) {
+		let passwords = &self.options.passwords;
+		if passwords.is_empty() || !crate::crack::is_encrypted(r) { return; }
+		// 标题为工具名，如 "openstego:crack"；载荷所在的通道见上一行
+		let title = match r {
+			DetectResult::OpenStego { .. } => "openstego:crack",
+			_ => "wbstego:crack",
+		};
+		match crate::crack::crack(r, data, passwords) {
+			Some(c) => {
+				let cracked = DetectResult::Cracked { password: c.password, data: c.data };
+				self.show_title(title, true);
+				println!("{}", cracked);
+				results.push(self.finding(title, cracked));
+			}
+			None => {
+				if self.options.verbose > 0 {
+					self.show_title(title, true);
+					println!("{}", format!("password not found in {} words", passwords.len()).bright_black());
+				}
+			}
+		}
+	}
+
+	fn finding(&self, title: &str, result: DetectResult) -> Finding {
+		Finding { title: format!("{}{}", self.prefix, title), result }
+	}
+
 	fn show_title(&self, title: &str, _pad: bool) {
+		let title = if title.is_empty() { String::new() } else { format!("{}{}", self.prefix, title) };
 		print!("\r{:<20}.. ", title.color("bright black"));
 		let _ = std::io::Write::flush(&mut std::io::stdout());
 	}
@@ -302,13 +460,12 @@ impl<'a> Checker<'a> {
 			else if let Some(s) = start { if i - s >= min_len { return Some(DetectResult::PartialText { text: String::from_utf8_lossy(&data[s..i]).to_string(), offset: s }); } else { start = None; } }
 		}
 		// 检查末尾
-		if let Some(s) = start {
-			if data.len() - s >= min_len {
-				return Some(DetectResult::PartialText { 
-					text: String::from_utf8_lossy(&data[s..]).to_string(), 
-					offset: s 
-				});
-			}
+		if let Some(s) = start
+			&& data.len() - s >= min_len {
+			return Some(DetectResult::PartialText { 
+				text: String::from_utf8_lossy(&data[s..]).to_string(), 
+				offset: s 
+			});
 		}
 		None
 	}
@@ -319,7 +476,7 @@ impl<'a> Checker<'a> {
 			let mut dec = ZlibDecoder::new(slice);
 			let mut buf = Vec::new();
 			if dec.read_to_end(&mut buf).is_ok() && !buf.is_empty() {
-				return Some(DetectResult::Zlib { data: buf, offset, size: slice.len() });
+				return Some(DetectResult::Zlib { data: buf, offset });
 			}
 		}
 		None
@@ -338,20 +495,21 @@ impl<'a> Checker<'a> {
 		
 		// OpenStego 检测
 		if let Some(r) = Self::check_openstego(data) { return Some(r); }
+
+		// 常见 Python/Node LSB 脚本的长度前缀/分隔符/终止符
+		if let Some(r) = Self::check_lsb_tools(data) { return Some(r); }
 		
 		// WBStego 检测（只在特定条件下）
-		if self.options.bit_order == Some(crate::opts::BitOrder::Lsb) {
-			if let Some(r) = Self::check_wbstego(data, self.image.format == crate::imageio::ImgFormat::Bmp) {
-				return Some(r);
-			}
+		if self.options.bit_order == Some(crate::opts::BitOrder::Lsb)
+			&& let Some(r) = Self::check_wbstego(data, self.image.format == crate::imageio::ImgFormat::Bmp) {
+			return Some(r);
 		}
 		
 		// WholeText 检查应该在 file 命令之前，使用更低的最小长度 (min_str_len - 2)
 		let min_wholetext_len = self.options.min_str_len.saturating_sub(2);
 		if let Some(r) = Self::whole_text_check(data, min_wholetext_len) { return Some(r); }
-		if let Some(ref mut fc) = state.file_cmd {
-			if let Some(r) = fc.data2result(data) { return Some(r); }
-		}
+		if let Some(ref mut fc) = state.file_cmd
+			&& let Some(r) = fc.data2result(data) { return Some(r); }
 		if let Some(r) = Checker::zlib_try(data) { return Some(r); }
 		// 字符串搜索在整个数据上进行
 		if let Some(r) = Self::strings_first(data, self.options.min_str_len) { return Some(r); }
@@ -435,7 +593,7 @@ impl<'a> Checker<'a> {
 		
 		// 检查扩展名是否有效（7-bit ASCII，无通配符）
 		let ext_str = String::from_utf8_lossy(ext_bytes);
-		let is_valid_ext = ext_bytes.iter().all(|&b| b >= 0x20 && b <= 0x7e) 
+		let is_valid_ext = ext_bytes.iter().all(|&b| (0x20..=0x7e).contains(&b)) 
 			&& !ext_str.contains('*') 
 			&& !ext_str.contains('?');
 		
@@ -455,6 +613,204 @@ impl<'a> Checker<'a> {
 		None
 	}
 	
+	// steganography-png：尾部以原始字节写在最后一行末尾
+	// MAGIC(0x11 0x6b) + mode + 长度(u32 BE) + 名称 + 名称长度(1 字节)
+	pub fn check_steganography_png(image: &Image) -> Option<DetectResult> {
+		let rows = image.scanlines()?;
+		let last = rows.last()?;
+		let name_len = *last.last()? as usize;
+		let footer_len = 2 + 1 + 4 + name_len + 1;
+		if last.len() < footer_len { return None; }
+		let footer = &last[last.len() - footer_len..];
+		if footer[..2] != [0x11, 0x6b] { return None; }
+		let mode = footer[2];
+		if !matches!(mode, 1 | 2 | 4 | 6 | 8) { return None; }
+		let size = u32::from_be_bytes([footer[3], footer[4], footer[5], footer[6]]);
+		let name = String::from_utf8_lossy(&footer[7..7 + name_len]).to_string();
+		let data = Self::steganography_png_payload(&rows, mode as usize, size as usize, footer_len);
+		Some(DetectResult::SteganographyPng { mode, size, name, data })
+	}
+
+	fn steganography_png_payload(rows: &[Vec<u8>], mode: usize, size: usize, footer_len: usize) -> Vec<u8> {
+		// 每个数据字节拆成 ceil(8/mode) 组，高位在前写入字节的低位（mode 6 第二组只有 2 位）
+		// 数据按每行 cap 字节分块，组在行内等间距分布，间距 = row_len / (n*groups + 2*(groups-1))；
+		// 只有剩余数据还够再填满一行时才写下一行，最后不足两行的部分写在末行尾部之前，
+		// 间距 = 可用长度 / (n*groups)，放不下时原工具直接丢弃，图像中不存在这部分数据
+		let groups = 8usize.div_ceil(mode);
+		let pad = 2 * (groups - 1);
+		let row_len = rows[0].len();
+		let cap = (row_len.saturating_sub(pad) / groups).max(1);
+		let last = rows.len() - 1;
+		let mut out = Vec::with_capacity(size.min(row_len * rows.len()));
+		let mut y = 0;
+		while out.len() < size {
+			let remaining = size - out.len();
+			let (row, spacing, n) = if (remaining >= 2 * cap || y == 0) && y < last {
+				let n = remaining.min(cap);
+				(y, row_len / (n * groups + pad), n)
+			} else {
+				(last, (row_len - footer_len) / (remaining * groups), remaining)
+			};
+			if spacing == 0 { break; }
+			for i in 0..n {
+				let mut v = 0u8;
+				let mut left = 8;
+				for g in 0..groups {
+					let w = left.min(mode);
+					let b = rows[row][(i * groups + g) * spacing];
+					v = ((v as u16) << w) as u8 | (b & ((1u16 << w) - 1) as u8);
+					left -= w;
+				}
+				out.push(v);
+			}
+			if row == last { break; }
+			y += 1;
+		}
+		out
+	}
+
+	// 图像数据之外的隐藏字节：提示 + hexdump + 内容分析
+	fn check_hidden_data(&self, title: &str, notice: &str, data: &[u8], state: &mut CheckState, results: &mut Vec<Finding>) {
+		if data.is_empty() { return; }
+		// 显示提示信息
+		if self.options.verbose >= 0 {
+			println!("{} {}", "[?]".yellow(), notice);
+		}
+
+		self.show_title(title, true);
+
+		// 显示hexdump（始终显示，因为这是特殊数据）；默认只显示前 limit 字节，-v 时显示全部
+		let limit = if self.options.verbose > 0 { 0 } else { self.options.limit };
+		println!("\n{}", hexdump::dump(4, data, limit));
+
+		// 也尝试分析内容，搜索整个数据
+		let mut temp_opts = self.options.clone();
+		temp_opts.limit = 0;
+		let temp_checker = Checker { image: self.image, options: &temp_opts, prefix: self.prefix.clone() };
+		results.push(self.finding(title, DetectResult::ChunkAnomaly(notice.to_string())));
+		if let Some(r) = temp_checker.data2result(data, state)
+			&& !matches!(r, DetectResult::OneChar { .. }) {
+			println!("{}", r);
+			results.push(self.finding(title, r));
+		}
+
+		state.found_anything = true;
+	}
+
+	// PNG 编码器可以为每行任意选择 filter 类型，选择本身即可携带数据
+	fn check_scanline(&self, state: &mut CheckState, results: &mut Vec<Finding>) {
+		// Adam7 pass 的行已按顺序包含在主图像的 filter 字节中
+		if self.image.adam7_pass { return; }
+		let Some(filters) = self.image.filter_bytes() else { return; };
+		if let Some(note) = Self::scanline_note(&filters, self.options.verbose) {
+			println!("{} {}", "[?]".yellow(), note);
+		}
+
+		for (name, data) in Self::scanline_streams(self.image) {
+			state.need_cr = !self.process_result(&data, name, false, state, results);
+			state.found_anything |= !state.need_cr;
+		}
+	}
+
+	// filter 类型分布提示：非法类型总是提示；只用两种类型在普通编码器输出中也很常见，仅 verbose 下提示
+	fn scanline_note(filters: &[u8], verbose: i32) -> Option<String> {
+		let mut counts = [0usize; 256];
+		filters.iter().for_each(|&f| counts[f as usize] += 1);
+		let used: Vec<u8> = (0..=255u8).filter(|&f| counts[f as usize] > 0).collect();
+		let invalid = used.iter().any(|&f| f > 4);
+		if !invalid && verbose <= 0 { return None; }
+		let binary = used.len() == 2 && used.iter().all(|&f| counts[f as usize] * 8 >= filters.len());
+		let dist = used.iter().map(|&f| format!("{}={}", f, counts[f as usize])).collect::<Vec<_>>().join(" ");
+		let note = if invalid { " (invalid filter types)" } else if binary { " (only 2 filter types)" } else { "" };
+		Some(format!("scanline filters: {}{}", dist, note))
+	}
+
+	// scanline 数据流：原始 filter 字节、filter 最低位、只用两种类型时按大小映射为 0/1
+	// 位按高位在前组成字节，不足 8 位的尾部丢弃
+	pub fn scanline_streams(image: &Image) -> Vec<(&'static str, Vec<u8>)> {
+		let Some(filters) = image.filter_bytes() else { return Vec::new(); };
+		let pack = |bits: Vec<u8>| -> Vec<u8> {
+			bits.chunks_exact(8).map(|c| c.iter().fold(0u8, |v, &b| (v << 1) | b)).collect()
+		};
+		let mut streams = vec![
+			("scanline:lsb", pack(filters.iter().map(|f| f & 1).collect())),
+		];
+		let mut used = filters.clone();
+		used.sort_unstable();
+		used.dedup();
+		if used.len() == 2 {
+			streams.push(("scanline:bin", pack(filters.iter().map(|&f| (f == used[1]) as u8).collect())));
+		}
+		streams.insert(0, ("scanline", filters));
+		streams
+	}
+
+	fn check_lsb_tools(data: &[u8]) -> Option<DetectResult> {
+		let is_print = |b: &u8| matches!(*b, 0x20..=0x7e | b'\r' | b'\n' | b'\t');
+		let lsb_tool = |tool: &str, size: Option<usize>, payload: &[u8]| Some(DetectResult::LsbTool {
+			tool: tool.to_string(),
+			size,
+			data: payload.to_vec(),
+		});
+
+		// stegano (lsb.hide)："<len>:<message>"
+		let digits = data.iter().take_while(|b| b.is_ascii_digit()).count();
+		if (1..=9).contains(&digits) && data[0] != b'0' && data.get(digits) == Some(&b':') {
+			let size: usize = std::str::from_utf8(&data[..digits]).ok()?.parse().ok()?;
+			let body = &data[digits + 1..];
+			let payload = &body[..size.min(body.len())];
+			if !payload.is_empty() && payload.iter().all(is_print) {
+				return lsb_tool("stegano", Some(size), payload);
+			}
+		}
+
+		// stegpy："stegv3" + 32 位大端长度 + 文件名长度（1 字节，文本消息为 0）+ 文件名 + 内容
+		if let Some(rest) = data.strip_prefix(b"stegv3") && rest.len() >= 5 {
+			let size = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+			let name_end = 5 + rest[4] as usize;
+			if let Some(name) = rest.get(5..name_end) {
+				let body = &rest[name_end..];
+				let tool = if name.is_empty() { "stegpy".to_string() } else { format!("stegpy, file {:?}", String::from_utf8_lossy(name)) };
+				return lsb_tool(&tool, Some(size), &body[..size.min(body.len())]);
+			}
+		}
+
+		// LSBSteg (encode_text)：16 位大端长度 + 文本（通常位于 bgr 通道）
+		// 长度高字节不可打印，避免把普通文本误判为长度；声明的长度必须完整且全部可打印
+		if data.len() > 2 && data[0] < 0x20 {
+			let size = u16::from_be_bytes([data[0], data[1]]) as usize;
+			if let Some(payload) = data.get(2..2 + size)
+				&& size >= 4 && payload.iter().all(is_print) {
+				return lsb_tool("LSBSteg", Some(size), payload);
+			}
+		}
+
+		// cloacked-pixel：32 位小端长度 + AES-CBC 密文（IV 16 字节 + 32 字节对齐的数据）
+		if data.len() > 4 {
+			let size = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+			if size >= 48 && size.is_multiple_of(16) && size < 1 << 20 {
+				let body = &data[4..];
+				// 密文应近似随机：前 64 字节中不同取值足够多
+				let sample = &body[..64.min(size).min(body.len())];
+				let mut seen = [false; 256];
+				sample.iter().for_each(|&b| seen[b as usize] = true);
+				if sample.len() >= 48 && seen.iter().filter(|&&x| x).count() * 4 >= sample.len() * 3 {
+					return lsb_tool("cloacked-pixel (encrypted)", Some(size), &body[..size.min(body.len())]);
+				}
+			}
+		}
+
+		// 以终止符结尾的文本：$t3g0 见于基于 PIL 的 Image-Steganography 脚本，##### 见于基于 OpenCV 的同类教程脚本
+		for (term, tool) in [(b"$t3g0".as_slice(), "Image-Steganography (PIL)"), (b"#####".as_slice(), "Image_Steganography (OpenCV)")] {
+			if let Some(pos) = data.windows(term.len()).position(|w| w == term)
+				&& pos > 0 && data[..pos].iter().all(is_print) {
+				return lsb_tool(tool, Some(pos), &data[..pos]);
+			}
+		}
+
+		None
+	}
+
 	fn check_openstego(data: &[u8]) -> Option<DetectResult> {
 		// 搜索 "OPENSTEGO" 字符串
 		const MAGIC: &[u8] = b"OPENSTEGO";
@@ -502,6 +858,89 @@ impl<'a> Checker<'a> {
 	}
 }
 
-fn default_channels() -> Vec<String> {
+fn default_channels(image: &Image) -> Vec<String> {
+	// 音频：各声道单独扫描，再按正序和逆序组合所有声道
+	if let Some(audio) = &image.audio {
+		let mut names = audio.channel_names();
+		if names.len() > 1 {
+			names.push(names.concat());
+			names.push(names[..names.len() - 1].iter().rev().cloned().collect::<Vec<_>>().concat());
+		}
+		return names;
+	}
 	vec!["r".into(), "g".into(), "b".into(), "rgb".into(), "bgr".into()]
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn lsb_tool(data: &[u8]) -> Option<(String, Option<usize>, Vec<u8>)> {
+		match Checker::check_lsb_tools(data)? {
+			DetectResult::LsbTool { tool, size, data } => Some((tool, size, data)),
+			_ => None,
+		}
+	}
+
+	#[test]
+	fn stegpy_payload_is_trimmed() {
+		let mut data = b"stegv3\0\0\0\x05\0hello".to_vec();
+		data.extend_from_slice(&[0xA5; 64]);
+		assert_eq!(lsb_tool(&data), Some(("stegpy".to_string(), Some(5), b"hello".to_vec())));
+
+		let data = b"stegv3\0\0\0\x02\x05a.txtok\xFF\xFF";
+		assert_eq!(lsb_tool(data), Some(("stegpy, file \"a.txt\"".to_string(), Some(2), b"ok".to_vec())));
+	}
+
+	#[test]
+	fn lsbsteg_needs_full_printable_payload() {
+		assert_eq!(lsb_tool(b"\0\x05hello\x93\x01"), Some(("LSBSteg".to_string(), Some(5), b"hello".to_vec())));
+		// 声明的长度超出数据，或只有开头几个字节可打印
+		assert_eq!(lsb_tool(b"\x01\x05hello"), None);
+		let mut noise = b"\0\x40abcd".to_vec();
+		noise.extend(0x80..=0xFFu8);
+		assert_eq!(lsb_tool(&noise), None);
+	}
+
+	#[test]
+	fn terminators_report_the_tool() {
+		assert_eq!(lsb_tool(b"hello$t3g0\x8a\x13"), Some(("Image-Steganography (PIL)".to_string(), Some(5), b"hello".to_vec())));
+		assert_eq!(lsb_tool(b"flag{x}#####\xff"), Some(("Image_Steganography (OpenCV)".to_string(), Some(7), b"flag{x}".to_vec())));
+		assert_eq!(lsb_tool(b"$t3g0hello"), None);
+	}
+
+	#[test]
+	fn two_filter_types_only_noted_when_verbose() {
+		// 常见编码器输出：None/Paeth 交替
+		let filters: Vec<u8> = (0..64).map(|i| if i % 3 == 0 { 0 } else { 4 }).collect();
+		assert_eq!(Checker::scanline_note(&filters, 0), None);
+		assert_eq!(Checker::scanline_note(&filters, 1).as_deref(), Some("scanline filters: 0=22 4=42 (only 2 filter types)"));
+		assert_eq!(Checker::scanline_note(&[0, 1, 7, 0], 0).as_deref(), Some("scanline filters: 0=2 1=1 7=1 (invalid filter types)"));
+	}
+
+	fn steganography_png(name: &str) -> (u8, u32, Vec<u8>) {
+		let path = format!("{}/samples/steganography-png/{}", env!("CARGO_MANIFEST_DIR"), name);
+		let img = Image::load(std::path::Path::new(&path)).unwrap();
+		match Checker::check_steganography_png(&img) {
+			Some(DetectResult::SteganographyPng { mode, size, name, data }) => {
+				assert_eq!(name, "text/plain.txt");
+				(mode, size, data)
+			}
+			r => panic!("{}: {:?}", path, r),
+		}
+	}
+
+	#[test]
+	fn steganography_png_samples() {
+		for (name, mode) in [
+			("logo1.png", 1), ("logo2.png", 2), ("logo4.png", 4), ("logo6.png", 6), ("logo8.png", 8),
+			("mouse1.png", 1), ("mouse2.png", 2), ("mouse4.png", 4), ("mouse6.png", 6), ("mouse8.png", 8),
+		] {
+			assert_eq!(steganography_png(name), (mode, 13, b"Hello, world!".to_vec()), "{}", name);
+		}
+		// 前 22 行各 10 字节；剩余 11 字节放不下末行，原工具未写入
+		let text = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et \
+			dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo";
+		assert_eq!(steganography_png("mouse1-long.png"), (1, 231, text.to_vec()));
+	}
+}
@@ -0,0 +1,233 @@
+use crate::result::DetectResult;
+use des::cipher::{BlockCipher, BlockDecrypt, BlockEncrypt, KeyInit, generic_array::GenericArray};
+use flate2::read::GzDecoder;
+use md5::{Digest, Md5};
+use sha1::Sha1;
+use std::io::Read;
+
+// 字典攻击：对检测到的加密载荷逐个尝试口令，解密结果通过有效性检查即停止
+pub struct Cracked {
+	pub password: String,
+	pub data: Vec<u8>,
+}
+
+// 是否存在可用于字典攻击的解码器
+pub fn is_encrypted(result: &DetectResult) -> bool {
+	match result {
+		DetectResult::OpenStego { encrypt, .. } => *encrypt != 0,
+		DetectResult::WBStego { enc: Some(enc), .. } => WBSTEGO_CIPHERS.contains(&enc.as_str()),
+		_ => false,
+	}
+}
+
+// wbStego 4.x 可选的分组密码；2.x/3.x 自带的加密与 mix 模式尚不支持
+const WBSTEGO_CIPHERS: &[&str] = &["Blowfish", "Twofish", "CAST128", "Rijndael"];
+
+pub fn crack(result: &DetectResult, stream: &[u8], words: &[String]) -> Option<Cracked> {
+	match result {
+		DetectResult::OpenStego { encrypt, compress, data_len, fname_len, .. } if *encrypt != 0 => {
+			const MAGIC: &[u8] = b"OPENSTEGO";
+			let pos = stream.windows(MAGIC.len()).position(|w| w == MAGIC)?;
+			// 头部：MAGIC + version + data_len(4) + channel_bits + fname_len + compress + encrypt + fname
+			let start = pos + MAGIC.len() + 9 + *fname_len as usize;
+			let end = start.saturating_add(*data_len as usize).min(stream.len());
+			if start >= end { return None; }
+			let ct = &stream[start..end];
+			let complete = end - start == *data_len as usize;
+			words.iter().find_map(|w| {
+				let plain = openstego_decrypt(ct, w, complete)?;
+				let data = if *compress != 0 { gunzip(&plain, complete)? } else { plain };
+				if *compress == 0 && !is_valid_plaintext(&data) { return None; }
+				Some(Cracked { password: w.clone(), data })
+			})
+		}
+		DetectResult::WBStego { size, enc: Some(enc), .. } => {
+			// 头部：size(3) + 00 FF + 头长度 + 头（首字节为算法编号）+ 密文
+			let start = 6 + *stream.get(5)? as usize;
+			let end = (3 + *size as usize).min(stream.len());
+			if start >= end { return None; }
+			let ct = &stream[start..end];
+			let complete = end == 3 + *size as usize;
+			words.iter().find_map(|w| {
+				let plain = wbstego_decrypt(enc, ct, w, complete)?;
+				// 明文为 3 字节扩展名 + 文件内容
+				let (ext, data) = plain.split_at_checked(3)?;
+				if !ext.iter().all(|&b| (0x20..=0x7e).contains(&b) && b != b'*' && b != b'?') || !is_valid_plaintext(data) {
+					return None;
+				}
+				Some(Cracked { password: w.clone(), data: data.to_vec() })
+			})
+		}
+		_ => None,
+	}
+}
+
+// wbStego 4.x 使用 DCPcrypt 1.x：密钥为口令的 SHA-1（截断到算法的最大密钥长度，
+// Rijndael/Twofish 不足的部分补 0），IV 为全 0xFF 块加密一次，CBC 模式
+fn wbstego_decrypt(enc: &str, ct: &[u8], password: &str, complete: bool) -> Option<Vec<u8>> {
+	let digest = Sha1::digest(password.as_bytes());
+	let mut key = [0u8; 24];
+	key[..20].copy_from_slice(&digest);
+	Some(match enc {
+		"Blowfish" => dcp_cbc(&blowfish::Blowfish::<byteorder::BE>::new_from_slice(&key[..20]).ok()?, ct, complete),
+		"Twofish" => dcp_cbc(&twofish::Twofish::new_from_slice(&key).ok()?, ct, complete),
+		"CAST128" => dcp_cbc(&cast5::Cast5::new_from_slice(&key[..16]).ok()?, ct, complete),
+		"Rijndael" => dcp_cbc(&aes::Aes192::new_from_slice(&key).ok()?, ct, complete),
+		_ => return None,
+	})
+}
+
+fn dcp_cbc<C: BlockCipher + BlockEncrypt + BlockDecrypt>(cipher: &C, ct: &[u8], complete: bool) -> Vec<u8> {
+	let bs = C::block_size();
+	let mut prev = GenericArray::clone_from_slice(&vec![0xFF; bs]);
+	cipher.encrypt_block(&mut prev);
+	let mut out = Vec::with_capacity(ct.len());
+	let mut blocks = ct.chunks_exact(bs);
+	for block in &mut blocks {
+		let mut b = GenericArray::clone_from_slice(block);
+		cipher.decrypt_block(&mut b);
+		out.extend(b.iter().zip(prev.iter()).map(|(x, y)| x ^ y));
+		prev.copy_from_slice(block);
+	}
+	// 不足一块的尾部与上一块密文的加密结果异或；截断的载荷无法判断尾部是否完整
+	let tail = blocks.remainder();
+	if complete && !tail.is_empty() {
+		cipher.encrypt_block(&mut prev);
+		out.extend(tail.iter().zip(prev.iter()).map(|(x, y)| x ^ y));
+	}
+	out
+}
+
+// OpenStego 0.5.x：PBEWithMD5AndDES（PKCS#5 PBES1），固定 salt，迭代 7 次，DES-CBC
+fn openstego_decrypt(ct: &[u8], password: &str, complete: bool) -> Option<Vec<u8>> {
+	const SALT: [u8; 8] = [0x28, 0x5F, 0x71, 0xC9, 0x1E, 0x35, 0x0A, 0x62];
+	const ITER_COUNT: usize = 7;
+	if ct.len() < 8 { return None; }
+
+	let mut h = Md5::new();
+	h.update(password.as_bytes());
+	h.update(SALT);
+	let mut dk = h.finalize();
+	for _ in 1..ITER_COUNT { dk = Md5::digest(dk); }
+
+	let cipher = des::Des::new(GenericArray::from_slice(&dk[..8]));
+	let mut prev = [0u8; 8];
+	prev.copy_from_slice(&dk[8..16]);
+	let mut out = Vec::with_capacity(ct.len());
+	for block in ct.chunks_exact(8) {
+		let mut b = GenericArray::clone_from_slice(block);
+		cipher.decrypt_block(&mut b);
+		out.extend(b.iter().zip(prev.iter()).map(|(x, y)| x ^ y));
+		prev.copy_from_slice(block);
+	}
+
+	// 完整密文时校验 PKCS#5 填充
+	if complete {
+		let pad = *out.last()? as usize;
+		if pad == 0 || pad > 8 || !ct.len().is_multiple_of(8) || !out[out.len() - pad..].iter().all(|&b| b as usize == pad) {
+			return None;
+		}
+		out.truncate(out.len() - pad);
+	}
+	Some(out)
+}
+
+fn gunzip(data: &[u8], complete: bool) -> Option<Vec<u8>> {
+	if !data.starts_with(&[0x1f, 0x8b, 0x08]) { return None; }
+	let mut dec = GzDecoder::new(data);
+	let mut out = Vec::new();
+	match dec.read_to_end(&mut out) {
+		Ok(_) => Some(out),
+		// 截断的载荷只能解出前半部分
+		Err(_) if !complete && !out.is_empty() => Some(out),
+		Err(_) => None,
+	}
+}
+
+// 解密结果的有效性检查：已知魔数或可打印文本
+pub fn is_valid_plaintext(data: &[u8]) -> bool {
+	const MAGICS: &[&[u8]] = &[
+		b"\x1f\x8b", b"\x78\x9c", b"\x78\x01", b"\x78\xda", b"\x89PNG", b"\xff\xd8\xff", b"GIF8", b"BM",
+		b"PK\x03\x04", b"%PDF", b"Rar!", b"7z\xbc\xaf", b"\x7fELF", b"MZ",
+	];
+	if data.is_empty() { return false; }
+	if MAGICS.iter().any(|m| data.starts_with(m)) { return true; }
+	let sample = &data[..data.len().min(256)];
+	let printable = sample.iter().filter(|&&b| matches!(b, 0x20..=0x7e | b'\r' | b'\n' | b'\t')).count();
+	printable * 100 >= sample.len() * 95
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// samples/wbstego 中各样本 b1,lsb,bY 提取出的数据，明文为 "txt" + "SuperSecretMessage\n"
+	fn wbstego(enc: &str, stream: &str) -> (DetectResult, Vec<u8>) {
+		let stream: Vec<u8> = (0..stream.len()).step_by(2).map(|i| u8::from_str_radix(&stream[i..i + 2], 16).unwrap()).collect();
+		let size = u32::from_le_bytes([stream[0], stream[1], stream[2], 0]);
+		(DetectResult::WBStego { size, ext: None, data_preview: Vec::new(), enc: Some(enc.to_string()), even: false }, stream)
+	}
+
+	#[test]
+	fn wbstego_ciphers() {
+		let words = ["foo".to_string(), "1".to_string(), "pass".to_string()];
+		for (enc, stream, password) in [
+			("Blowfish", "1a000000ff01010d43f5bf23ff5b361bb317424a3fbaebc7ee9cd77a2b0000", "1"),
+			("Twofish", "1a000000ff0102cb6678eaa994f6f5cc8862a0662e91b53283bf5730800000", "1"),
+			("CAST128", "1a000000ff01034a0636c79a665c28404f0b53b746665bc3aaa40a51430000", "1"),
+			("Rijndael", "1a000000ff01048de3b9d1a46f18a044d84281209cb1bb30581612fc620000", "1"),
+			("Rijndael", "1a000000ff01046831945144c83e23e0f2b1b1836b67014eb3325876e60000", "pass"),
+		] {
+			let (r, stream) = wbstego(enc, stream);
+			assert!(is_encrypted(&r));
+			let c = crack(&r, &stream, &words).unwrap_or_else(|| panic!("{} not cracked", enc));
+			assert_eq!((c.password.as_str(), c.data.as_slice()), (password, b"SuperSecretMessage\n".as_slice()));
+			assert!(crack(&r, &stream, &words[..1]).is_none());
+		}
+	}
+
+	// OpenStego 0.5.x 的加密：同 openstego_decrypt 的 PBEWithMD5AndDES，PKCS#5 填充
+	fn openstego_encrypt(plain: &[u8], password: &str) -> Vec<u8> {
+		let mut h = Md5::new();
+		h.update(password.as_bytes());
+		h.update([0x28, 0x5F, 0x71, 0xC9, 0x1E, 0x35, 0x0A, 0x62]);
+		let mut dk = h.finalize();
+		for _ in 1..7 { dk = Md5::digest(dk); }
+		let cipher = des::Des::new(GenericArray::from_slice(&dk[..8]));
+		let pad = 8 - plain.len() % 8;
+		let mut data = plain.to_vec();
+		data.extend(std::iter::repeat_n(pad as u8, pad));
+		let mut prev = GenericArray::clone_from_slice(&dk[8..16]);
+		let mut out = Vec::new();
+		for block in data.chunks_exact(8) {
+			let mut b = GenericArray::clone_from_slice(block);
+			b.iter_mut().zip(prev.iter()).for_each(|(x, y)| *x ^= y);
+			cipher.encrypt_block(&mut b);
+			out.extend_from_slice(&b);
+			prev = b;
+		}
+		out
+	}
+
+	#[test]
+	fn openstego_pbe_md5_des() {
+		let words = ["foo".to_string(), "secret".to_string()];
+		let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+		std::io::Write::write_all(&mut gz, b"SuperSecretMessage\n").unwrap();
+		let gz = gz.finish().unwrap();
+		for (compress, plain) in [(0u8, b"SuperSecretMessage\n".to_vec()), (1, gz)] {
+			let ct = openstego_encrypt(&plain, "secret");
+			// 头部：MAGIC + version + data_len(4) + channel_bits + fname_len + compress + encrypt + fname
+			let mut stream = b"\x00OPENSTEGO\x02".to_vec();
+			stream.extend_from_slice(&(ct.len() as u32).to_le_bytes());
+			stream.extend_from_slice(&[1, 5, compress, 1]);
+			stream.extend_from_slice(b"a.txt");
+			stream.extend_from_slice(&ct);
+			let r = DetectResult::OpenStego { version: 2, data_len: ct.len() as u32, channel_bits: 1, fname_len: 5, compress, encrypt: 1, fname: "a.txt".into() };
+			assert!(is_encrypted(&r));
+			let c = crack(&r, &stream, &words).unwrap();
+			assert_eq!((c.password.as_str(), c.data.as_slice()), ("secret", b"SuperSecretMessage\n".as_slice()));
+			assert!(crack(&r, &stream, &words[..1]).is_none());
+		}
+	}
+}
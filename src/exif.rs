@@ -0,0 +1,114 @@
+// EXIF（TIFF 结构）解析：遍历 IFD0、Exif/GPS/Interop IFD 与 IFD1，取出可能藏数据的标签
+// 返回 (标签名, 值) 列表，值为原始字节（ASCII 去除结尾 NUL，UserComment 去除字符集头）
+
+const EXIF_IFD: u16 = 0x8769;
+const GPS_IFD: u16 = 0x8825;
+const INTEROP_IFD: u16 = 0xA005;
+const THUMB_OFFSET: u16 = 0x0201;
+const THUMB_LENGTH: u16 = 0x0202;
+
+pub fn tag_name(tag: u16) -> String {
+	match tag {
+		0x010D => "DocumentName",
+		0x010E => "ImageDescription",
+		0x010F => "Make",
+		0x0110 => "Model",
+		0x0131 => "Software",
+		0x0132 => "DateTime",
+		0x013B => "Artist",
+		0x013C => "HostComputer",
+		0x8298 => "Copyright",
+		0x9003 => "DateTimeOriginal",
+		0x9004 => "DateTimeDigitized",
+		0x927C => "MakerNote",
+		0x9286 => "UserComment",
+		0xA420 => "ImageUniqueID",
+		0xA430 => "CameraOwnerName",
+		0xA431 => "BodySerialNumber",
+		0xA434 => "LensModel",
+		0x9C9B => "XPTitle",
+		0x9C9C => "XPComment",
+		0x9C9D => "XPAuthor",
+		0x9C9E => "XPKeywords",
+		0x9C9F => "XPSubject",
+		_ => return format!("tag 0x{:04X}", tag),
+	}
+	.to_string()
+}
+
+pub fn parse(data: &[u8]) -> Vec<(String, Vec<u8>)> {
+	let data = data.strip_prefix(b"Exif\0\0").unwrap_or(data);
+	let le = match data.get(..2) {
+		Some(b"II") => true,
+		Some(b"MM") => false,
+		_ => return Vec::new(),
+	};
+	let rd16 = |p: usize| data.get(p..p + 2).map(|b| if le { u16::from_le_bytes([b[0], b[1]]) } else { u16::from_be_bytes([b[0], b[1]]) });
+	let rd32 = |p: usize| data.get(p..p + 4).map(|b| {
+		let b = [b[0], b[1], b[2], b[3]];
+		if le { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) }
+	});
+
+	let mut out = Vec::new();
+	let mut queue: Vec<usize> = rd32(4).map(|o| vec![o as usize]).unwrap_or_default();
+	let mut visited = Vec::new();
+	let mut thumb = (None, None);
+	while let Some(ifd) = queue.pop() {
+		// 防止循环引用
+		if ifd == 0 || visited.contains(&ifd) || visited.len() > 16 { continue; }
+		visited.push(ifd);
+		let Some(count) = rd16(ifd) else { continue; };
+		for i in 0..count as usize {
+			let e = ifd + 2 + i * 12;
+			let (Some(tag), Some(typ), Some(n)) = (rd16(e), rd16(e + 2), rd32(e + 4)) else { break; };
+			let unit = match typ { 1 | 2 | 6 | 7 => 1, 3 | 8 => 2, 4 | 9 | 11 => 4, 5 | 10 | 12 => 8, _ => continue };
+			let size = unit * n as usize;
+			let value = if size <= 4 {
+				data.get(e + 8..e + 8 + size)
+			} else {
+				rd32(e + 8).and_then(|o| data.get(o as usize..(o as usize).checked_add(size)?))
+			};
+			let Some(value) = value else { continue; };
+			match (tag, typ) {
+				(EXIF_IFD | GPS_IFD | INTEROP_IFD, _) => queue.extend(rd32(e + 8).map(|o| o as usize)),
+				(THUMB_OFFSET, _) => thumb.0 = rd32(e + 8),
+				(THUMB_LENGTH, _) => thumb.1 = rd32(e + 8),
+				// 字符串
+				(_, 2) => {
+					let v = value.iter().rposition(|&b| b != 0).map(|p| &value[..=p]).unwrap_or_default();
+					if !v.is_empty() { out.push((tag_name(tag), v.to_vec())); }
+				}
+				// UserComment：前 8 字节为字符集标识
+				(0x9286, 7) if value.len() > 8 => {
+					let v = &value[8..];
+					let v = if value.starts_with(b"UNICODE\0") { utf16(v, le) } else { v.to_vec() };
+					out.push((tag_name(tag), v));
+				}
+				// Windows XP* 标签：UTF-16LE
+				(0x9C9B..=0x9C9F, 1) => out.push((tag_name(tag), utf16(value, true))),
+				// 其他较长的未定义/字节数据（MakerNote 等）
+				(_, 1 | 7) if size > 4 => out.push((tag_name(tag), value.to_vec())),
+				_ => {}
+			}
+		}
+		// 只有 IFD0 的后继（IFD1，缩略图）需要继续
+		if visited.len() == 1 {
+			queue.extend(rd32(ifd + 2 + count as usize * 12).map(|o| o as usize));
+		}
+	}
+
+	if let (Some(off), Some(len)) = thumb
+		&& let Some(t) = data.get(off as usize..(off as usize).saturating_add(len as usize)) {
+		out.push(("Thumbnail".to_string(), t.to_vec()));
+	}
+	out
+}
+
+fn utf16(data: &[u8], le: bool) -> Vec<u8> {
+	let units: Vec<u16> = data
+		.chunks_exact(2)
+		.map(|c| if le { u16::from_le_bytes([c[0], c[1]]) } else { u16::from_be_bytes([c[0], c[1]]) })
+		.collect();
+	let s = String::from_utf16_lossy(&units);
+	s.trim_end_matches('\0').as_bytes().to_vec()
+}
@@ -1,5 +1,5 @@
 use crate::imageio::Image;
-use crate::opts::{BitOrder, Options, OrderSpec, PrimeSpec};
+use crate::opts::{BitOrder, ByteSource, Options, OrderSpec, PrimeSpec};
 
 // 从像素按通道与位顺序提取比特，聚合为字节序列
 pub fn extract(image: &Image, opts: &Options, title_out: &mut String) -> Vec<u8> {
@@ -10,12 +10,13 @@ pub fn extract(image: &Image, opts: &Options, title_out: &mut String) -> Vec<u8>
 		.map(|v| v.join(""))
 		.unwrap_or_else(|| "rgb".to_string());
 	let (channels, per_channel_bits) = parse_channels_pattern(&channels_pattern);
+	let offsets: Vec<Option<usize>> = channels.iter().map(|c| channel_offset(image, c)).collect();
 	let bits = opts.bits.clone().unwrap_or_else(|| vec![1]);
 
     *title_out = format!(
         "b{},{},{},{}",
 		if bits.len() == 1 { bits[0].to_string() } else { bits.iter().map(|b| b.to_string()).collect::<Vec<_>>().join("/") },
-		channels.iter().collect::<String>(),
+		channels.concat(),
         match bit_order { BitOrder::Lsb => "lsb", BitOrder::Msb => "msb" },
 		order_string(opts)
 	);
@@ -27,28 +28,21 @@ pub fn extract(image: &Image, opts: &Options, title_out: &mut String) -> Vec<u8>
 	// limit: 当输出达到 limit 字节时停止提取（原版行为）
 	let limit = if opts.limit > 0 { opts.limit } else { usize::MAX };
 
-	let pixels = &image.pixels; // RGBA8
-	let has_alpha = true;
-	let stride = if has_alpha { 4 } else { 3 }; // 实际此处固定 RGBA8
+	let pixels = &image.pixels; // RGBA8；音频为每帧各声道一个字节
+	let stride = image.audio.as_ref().map_or(4, |a| a.channels);
 
 	match &opts.order {
 		OrderSpec::Explicit(v) if v.iter().any(|s| s.contains('b') || s.contains('B')) => {
-			// 字节迭代：从 imagedata (scanlines 原始字节) 中提取
+			// 字节迭代：从 imagedata（PNG 带 filter 字节）或 rawpixels（去除 filter）中提取
 			// 这是 WBStego 等工具使用的方式
-			let imagedata = &image.imagedata;
+			// JPEG 没有像素数据，默认使用 DCT 系数
+			let default_source = if image.format == crate::imageio::ImgFormat::Jpeg { ByteSource::Dct } else { ByteSource::ImageData };
+			let (imagedata, rows) = byte_source(image, opts.byte_source.unwrap_or(default_source));
+			let bytes_per_line = rows.iter().map(|&(_, len)| len).max().unwrap_or(0);
 			
 			// 解析order：bY, bX, BY, BX, yb, YB, xb, XB
 			let order_str = v.iter().find(|s| s.contains('b') || s.contains('B')).map(|s| s.as_str()).unwrap_or("bY");
-			let (x_start, x_end, x_step, y_start, y_end, y_step) = parse_byte_order(order_str, image);
-			
-			let bytes_per_line = if image.format == crate::imageio::ImgFormat::Bmp {
-				// BMP: 每行字节数 = width * bytes_per_pixel
-				// 对于24位BMP = width * 3，但需要考虑行对齐
-				((image.width as usize * 3 + 3) / 4) * 4
-			} else {
-				// PNG: imagedata 包含 scanline 数据，每行前有filter字节
-				(image.width as usize * 4) + 1 // RGBA + 1 filter byte
-			};
+			let (x_start, x_end, x_step, y_start, y_end, y_step) = parse_byte_order(order_str, bytes_per_line, rows.len());
 			
 			// 按照 Ruby byte_iterator 的逻辑：
 			// if type[0,1].downcase == 'b' => ROW iterator (natural): y0.step(y1,ystep){ |y| x0.step(x1,xstep){ |x| yield(x,y) }}
@@ -61,8 +55,10 @@ pub fn extract(image: &Image, opts: &Options, title_out: &mut String) -> Vec<u8>
 			if is_row_first {
 				// ROW iterator: 先遍历行(y)，再遍历字节(x)
 				'outer: for y in make_range(y_start, y_end, y_step) {
-					let line_start = y * bytes_per_line;
+					let (line_start, line_len) = rows[y];
 					for x in make_range(x_start, x_end, x_step) {
+						// 隔行 PNG 中较窄的 pass 行没有这一列
+						if x >= line_len { continue; }
 						let pos = line_start + x;
 						if pos >= imagedata.len() { break 'outer; }
 						
@@ -78,7 +74,8 @@ pub fn extract(image: &Image, opts: &Options, title_out: &mut String) -> Vec<u8>
 				// COLUMN iterator: 先遍历字节(x)，再遍历行(y)
 				'outer: for x in make_range(x_start, x_end, x_step) {
 					for y in make_range(y_start, y_end, y_step) {
-						let line_start = y * bytes_per_line;
+						let (line_start, line_len) = rows[y];
+						if x >= line_len { continue; }
 						let pos = line_start + x;
 						if pos >= imagedata.len() { break 'outer; }
 						
@@ -96,17 +93,38 @@ pub fn extract(image: &Image, opts: &Options, title_out: &mut String) -> Vec<u8>
 			'outer: for x in 0..image.width as usize {
 				for y in 0..image.height as usize {
 					let idx = (y * image.width as usize + x) * stride;
-					process_pixel(idx, pixels, &channels, &per_channel_bits, &bits, bit_order, &mut cur, &mut cur_bits, &mut out, use_prime_pixel(y, x, opts));
+					process_pixel(idx, pixels, &offsets, &per_channel_bits, &bits, bit_order, &mut cur, &mut cur_bits, &mut out, use_prime_pixel(y, x, opts));
 					if out.len() >= limit { break 'outer; }
 				}
 			}
 		}
+		OrderSpec::Explicit(v) if v.iter().any(|s| s == "adam7") => {
+			// Adam7 pass 顺序：依次遍历每个 pass 覆盖的像素（pass 内按行）
+			let (w, h) = (image.width as usize, image.height as usize);
+			'outer: for &(x0, y0, dx, dy) in crate::imageio::ADAM7.iter() {
+				for y in (y0..h).step_by(dy) {
+					for x in (x0..w).step_by(dx) {
+						process_pixel((y * w + x) * stride, pixels, &offsets, &per_channel_bits, &bits, bit_order, &mut cur, &mut cur_bits, &mut out, use_prime_pixel(y, x, opts));
+						if out.len() >= limit { break 'outer; }
+					}
+				}
+			}
+		}
+		OrderSpec::Random { prng, seed } => {
+			// 伪随机像素顺序：按 PRNG 生成的排列遍历像素
+			let w = image.width as usize;
+			for i in crate::prng::permutation(*prng, *seed, w * image.height as usize) {
+				let (y, x) = (i / w, i % w);
+				process_pixel(i * stride, pixels, &offsets, &per_channel_bits, &bits, bit_order, &mut cur, &mut cur_bits, &mut out, use_prime_pixel(y, x, opts));
+				if out.len() >= limit { break; }
+			}
+		}
 		_ => {
 			// 默认 xy
 			'outer: for y in 0..image.height as usize {
 				for x in 0..image.width as usize {
 					let idx = (y * image.width as usize + x) * stride;
-					process_pixel(idx, pixels, &channels, &per_channel_bits, &bits, bit_order, &mut cur, &mut cur_bits, &mut out, use_prime_pixel(y, x, opts));
+					process_pixel(idx, pixels, &offsets, &per_channel_bits, &bits, bit_order, &mut cur, &mut cur_bits, &mut out, use_prime_pixel(y, x, opts));
 					if out.len() >= limit { break 'outer; }
 				}
 			}
@@ -114,7 +132,7 @@ pub fn extract(image: &Image, opts: &Options, title_out: &mut String) -> Vec<u8>
 	}
 
 	// shift: 前置 N 个 0 比特（等价于将输出整体右移 N 位）
-	if let Some(shift) = opts.shift { if shift > 0 { out = shift_prepend_zero_bits(out, shift as u32); } }
+	if let Some(shift) = opts.shift && shift > 0 { out = shift_prepend_zero_bits(out, shift as u32); }
 	// invert: 对每个字节异或 0xFF
 	if opts.invert { for b in &mut out { *b ^= 0xFF; } }
 	out
@@ -124,9 +142,12 @@ fn order_string(opts: &Options) -> String {
 	match &opts.order {
 		OrderSpec::Auto => "auto".to_string(),
 		OrderSpec::All => "ALL".to_string(),
+		OrderSpec::Random { .. } => opts.order.to_string(),
 		OrderSpec::Explicit(v) => {
 			if v.iter().any(|s| s.eq_ignore_ascii_case("yx")) { 
 				"yx".to_string()
+			} else if v.iter().any(|s| s == "adam7") {
+				"adam7".to_string()
 			} else if let Some(s) = v.iter().find(|s| s.contains('b') || s.contains('B')) { 
 				s.clone()
 			} else { 
@@ -144,18 +165,32 @@ fn make_range(start: usize, end: usize, step: usize) -> Box<dyn Iterator<Item =
 	}
 }
 
-fn parse_byte_order(order: &str, image: &Image) -> (usize, usize, usize, usize, usize, usize) {
+// 字节迭代的数据及每行的 (偏移, 长度)
+fn byte_source(image: &Image, source: ByteSource) -> (Vec<u8>, Vec<(usize, usize)>) {
+	let uniform = |line: usize, height: usize| (0..height).map(|y| (y * line, line)).collect();
+	let height = (image.height as usize).max(1);
+	match (image.format, image.ihdr()) {
+		// 系数去掉 0/1 后不再按块对齐，视为一整行
+		_ if source == ByteSource::Dct => {
+			let data = image.jpeg.as_ref().map(|j| crate::jpeg::jsteg_bytes(&j.coefficients)).unwrap_or_default();
+			let len = data.len();
+			(data, vec![(0, len)])
+		}
+		(crate::imageio::ImgFormat::Png, Some(ihdr)) if source == ByteSource::RawPixels => {
+			(image.rawpixels().unwrap_or_default(), uniform(ihdr.stride(), ihdr.height as usize))
+		}
+		(crate::imageio::ImgFormat::Png, Some(ihdr)) => (image.imagedata.clone(), ihdr.rows()),
+		// BMP：imagedata 为带 4 字节行对齐的原始像素，无 filter 字节
+		_ => (image.imagedata.clone(), uniform(image.imagedata.len() / height, height)),
+	}
+}
+
+fn parse_byte_order(order: &str, bytes_per_line: usize, height: usize) -> (usize, usize, usize, usize, usize, usize) {
 	// 解析类似 "bY", "BY", "yb", "Yb" 等
 	// 'b'/'B' 表示字节（x方向），'y'/'Y' 表示行（y方向）
 	// 小写表示正向（0->max），大写表示逆向（max->0）
 	
-	let bytes_per_line = if image.format == crate::imageio::ImgFormat::Bmp {
-		((image.width as usize * 3 + 3) / 4) * 4
-	} else {
-		(image.width as usize * 4) + 1
-	};
-	
-	let max_y = (image.height as usize).saturating_sub(1);
+	let max_y = height.saturating_sub(1);
 	let max_x = bytes_per_line.saturating_sub(1);
 	
 	// 默认值 (正向)
@@ -179,32 +214,42 @@ fn parse_byte_order(order: &str, image: &Image) -> (usize, usize, usize, usize,
 	(x_start, x_end, x_step, y_start, y_end, y_step)
 }
 
-fn parse_channels_pattern(p: &str) -> (Vec<char>, Vec<u8>) {
-	// 支持 "rgb" 或 "r3g2b3" 形式
+fn parse_channels_pattern(p: &str) -> (Vec<String>, Vec<u8>) {
+	// 支持 "rgb" 或 "r3g2b3" 形式；音频声道另有 "l"、"r" 与 "c0c1" 形式的声道号
 	let chars: Vec<char> = p.chars().collect();
-	if chars.len() % 2 == 0 && chars.chunks(2).all(|c| matches!(c[0], 'r'|'g'|'b'|'a') && c[1].is_ascii_digit()) {
+	if chars.len().is_multiple_of(2) && chars.chunks(2).all(|c| matches!(c[0], 'r'|'g'|'b'|'a'|'l') && c[1].is_ascii_digit()) {
 		let mut chs = Vec::new();
 		let mut bits = Vec::new();
 		for i in (0..chars.len()).step_by(2) {
-			chs.push(chars[i]);
-			bits.push((chars[i+1] as u8 - b'0') as u8);
+			chs.push(chars[i].to_string());
+			bits.push(chars[i+1] as u8 - b'0');
 		}
 		(chs, bits)
 	} else {
-		(chars, vec![])
+		let mut chs = Vec::new();
+		let mut i = 0;
+		while i < chars.len() {
+			let n = if chars[i] == 'c' { 1 + chars[i+1..].iter().take_while(|c| c.is_ascii_digit()).count() } else { 1 };
+			chs.push(chars[i..i+n].iter().collect());
+			i += n;
+		}
+		(chs, vec![])
+	}
+}
+
+// 通道在每个像素中的字节偏移：图像为 RGBA8，音频为声道序号
+fn channel_offset(image: &Image, name: &str) -> Option<usize> {
+	match &image.audio {
+		Some(audio) => audio.channel_index(name),
+		None => "rgba".find(name).filter(|_| name.len() == 1),
 	}
 }
 
-fn process_pixel(idx: usize, pixels: &[u8], channels: &[char], per_channel_bits: &[u8], bits: &[u16], bit_order: BitOrder, cur: &mut u8, cur_bits: &mut u8, out: &mut Vec<u8>, use_it: bool) {
+#[allow(clippy::too_many_arguments)]
+fn process_pixel(idx: usize, pixels: &[u8], offsets: &[Option<usize>], per_channel_bits: &[u8], bits: &[u16], bit_order: BitOrder, cur: &mut u8, cur_bits: &mut u8, out: &mut Vec<u8>, use_it: bool) {
 	if !use_it { return; }
-	for (i, ch) in channels.iter().enumerate() {
-		let v = match *ch {
-			'r' => pixels[idx],
-			'g' => pixels[idx + 1],
-			'b' => pixels[idx + 2],
-			'a' => pixels[idx + 3],
-			_ => 0,
-		};
+	for (i, off) in offsets.iter().enumerate() {
+		let v = off.map_or(0, |o| pixels[idx + o]);
 		if !per_channel_bits.is_empty() {
 			let nb = per_channel_bits.get(i).copied().unwrap_or(1) as u16;
 			push_nbits(out, cur, cur_bits, v, nb, bit_order);
@@ -277,9 +322,9 @@ fn use_prime_pixel(y: usize, x: usize, opts: &Options) -> bool {
 
 fn is_prime(n: u64) -> bool {
 	if n < 2 { return false; }
-	if n % 2 == 0 { return n == 2; }
+	if n.is_multiple_of(2) { return n == 2; }
 	let mut d = 3;
-	while d * d <= n { if n % d == 0 { return false; } d += 2; }
+	while d * d <= n { if n.is_multiple_of(d) { return false; } d += 2; }
 	true
 }
 
@@ -0,0 +1,198 @@
+// GIF 块结构解析：逻辑屏幕、全局/局部调色板、各帧 LZW 解码后的调色板索引、
+// 扩展块（注释、应用、纯文本）以及结束符 0x3B 之后的数据
+use anyhow::{bail, Context, Result};
+
+#[derive(Debug, Clone)]
+pub struct Frame {
+	pub left: usize,
+	pub top: usize,
+	pub width: usize,
+	pub height: usize,
+	pub palette: Option<Vec<u8>>, // 局部调色板（RGB 三元组）
+	pub indices: Vec<u8>, // 按行排列（已去隔行）的调色板索引
+}
+
+#[derive(Debug, Clone)]
+pub struct Gif {
+	pub width: usize,
+	pub height: usize,
+	pub background: u8,
+	pub palette: Option<Vec<u8>>, // 全局调色板
+	pub frames: Vec<Frame>,
+	pub extensions: Vec<(String, Vec<u8>)>, // (名称, 拼接后的子块数据)
+	pub trailer: Vec<u8>, // 结束符之后的数据
+}
+
+pub fn parse(data: &[u8]) -> Result<Gif> {
+	if !data.starts_with(b"GIF87a") && !data.starts_with(b"GIF89a") {
+		bail!("Not a GIF file");
+	}
+	let screen = data.get(6..13).context("truncated GIF header")?;
+	let mut gif = Gif {
+		width: u16::from_le_bytes([screen[0], screen[1]]) as usize,
+		height: u16::from_le_bytes([screen[2], screen[3]]) as usize,
+		background: screen[5],
+		palette: None,
+		frames: Vec::new(),
+		extensions: Vec::new(),
+		trailer: Vec::new(),
+	};
+	if gif.width * gif.height > 1 << 26 {
+		bail!("bad GIF size {}x{}", gif.width, gif.height);
+	}
+	let mut pos = 13;
+	if screen[4] & 0x80 != 0 {
+		gif.palette = Some(read_palette(data, &mut pos, screen[4])?);
+	}
+
+	while let Some(&tag) = data.get(pos) {
+		pos += 1;
+		match tag {
+			// 扩展块
+			0x21 => {
+				let label = *data.get(pos).context("truncated extension")?;
+				pos += 1;
+				let blocks = read_blocks(data, &mut pos);
+				let name = match label {
+					0xFE => "comment".to_string(),
+					// 前 11 字节为应用标识 + 认证码，作为名称的一部分
+					0xFF if blocks.len() >= 11 => {
+						let id = String::from_utf8_lossy(&blocks[..11]).to_string();
+						gif.extensions.push((format!("application {}", id), blocks[11..].to_vec()));
+						continue;
+					}
+					0x01 if blocks.len() >= 12 => {
+						gif.extensions.push(("plain text".to_string(), blocks[12..].to_vec()));
+						continue;
+					}
+					// 图形控制扩展为固定 4 字节，不含可藏数据的字段
+					0xF9 => continue,
+					_ => format!("extension 0x{:02X}", label),
+				};
+				gif.extensions.push((name, blocks));
+			}
+			// 图像描述符
+			0x2C => {
+				let d = data.get(pos..pos + 9).context("truncated image descriptor")?;
+				pos += 9;
+				let rd = |i: usize| u16::from_le_bytes([d[i], d[i + 1]]) as usize;
+				let (left, top, width, height) = (rd(0), rd(2), rd(4), rd(6));
+				if width * height > 1 << 26 {
+					bail!("bad GIF frame size {}x{}", width, height);
+				}
+				let palette = if d[8] & 0x80 != 0 { Some(read_palette(data, &mut pos, d[8])?) } else { None };
+				let min_code = *data.get(pos).context("truncated image data")?;
+				pos += 1;
+				let lzw = read_blocks(data, &mut pos);
+				let mut indices = weezl::decode::Decoder::new(weezl::BitOrder::Lsb, min_code.clamp(2, 11))
+					.decode(&lzw)
+					.unwrap_or_default();
+				indices.resize(width * height, 0);
+				if d[8] & 0x40 != 0 {
+					indices = deinterlace(&indices, width, height);
+				}
+				gif.frames.push(Frame { left, top, width, height, palette, indices });
+			}
+			0x3B => {
+				gif.trailer = data[pos..].to_vec();
+				break;
+			}
+			_ => bail!("unknown GIF block 0x{:02X} at offset {}", tag, pos - 1),
+		}
+	}
+	Ok(gif)
+}
+
+impl Gif {
+	// 帧的调色板索引铺到整个画布，未覆盖处填背景色索引
+	pub fn canvas_indices(&self, frame: &Frame) -> Vec<u8> {
+		let mut out = vec![self.background; self.width * self.height];
+		// 完全落在画布之外的帧没有可见像素
+		if frame.left >= self.width || frame.top >= self.height {
+			return out;
+		}
+		for y in 0..frame.height.min(self.height.saturating_sub(frame.top)) {
+			let w = frame.width.min(self.width.saturating_sub(frame.left));
+			let src = &frame.indices[y * frame.width..y * frame.width + w];
+			let dst = (frame.top + y) * self.width + frame.left;
+			out[dst..dst + w].copy_from_slice(src);
+		}
+		out
+	}
+}
+
+fn read_palette(data: &[u8], pos: &mut usize, flags: u8) -> Result<Vec<u8>> {
+	let len = 3 << ((flags & 7) + 1);
+	let p = data.get(*pos..*pos + len).context("truncated color table")?;
+	*pos += len;
+	Ok(p.to_vec())
+}
+
+// 读取以 0 长度结尾的子块序列，返回拼接后的数据
+fn read_blocks(data: &[u8], pos: &mut usize) -> Vec<u8> {
+	let mut out = Vec::new();
+	while let Some(&n) = data.get(*pos) {
+		*pos += 1;
+		if n == 0 { break; }
+		let end = (*pos + n as usize).min(data.len());
+		out.extend_from_slice(&data[*pos..end]);
+		*pos = end;
+	}
+	out
+}
+
+// 隔行存储顺序：0,8,16.. / 4,12.. / 2,6.. / 1,3..
+fn deinterlace(indices: &[u8], width: usize, height: usize) -> Vec<u8> {
+	let mut out = vec![0; indices.len()];
+	let mut rows = indices.chunks(width.max(1));
+	for (start, step) in [(0, 8), (4, 8), (2, 4), (1, 2)] {
+		for y in (start..height).step_by(step) {
+			if let Some(row) = rows.next() {
+				out[y * width..y * width + row.len()].copy_from_slice(row);
+			}
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// 逻辑屏幕 w x h，单帧位于 (left, top)，尺寸 fw x fh，图像数据只有 LZW 结束码
+	fn gif(w: u16, h: u16, left: u16, top: u16, fw: u16, fh: u16) -> Vec<u8> {
+		let mut out = b"GIF89a".to_vec();
+		for v in [w, h] {
+			out.extend_from_slice(&v.to_le_bytes());
+		}
+		out.extend_from_slice(&[0, 0, 0, 0x2C]);
+		for v in [left, top, fw, fh] {
+			out.extend_from_slice(&v.to_le_bytes());
+		}
+		out.extend_from_slice(&[0, 2, 1, 0x05, 0, 0x3B]);
+		out
+	}
+
+	#[test]
+	fn frame_outside_canvas() {
+		for (left, top) in [(20, 0), (0, 20), (20, 20), (4, 4)] {
+			let g = parse(&gif(4, 4, left, top, 2, 2)).unwrap();
+			assert_eq!(g.canvas_indices(&g.frames[0]), vec![0; 16]);
+		}
+	}
+
+	#[test]
+	fn frame_clipped_to_canvas() {
+		let mut g = parse(&gif(4, 4, 3, 3, 2, 2)).unwrap();
+		g.frames[0].indices = vec![1, 2, 3, 4];
+		let out = g.canvas_indices(&g.frames[0]);
+		assert_eq!(out[15], 1);
+		assert_eq!(out.iter().filter(|&&v| v != 0).count(), 1);
+	}
+
+	#[test]
+	fn oversized_screen_and_frame() {
+		assert!(parse(&gif(65535, 65535, 0, 0, 1, 1)).is_err());
+		assert!(parse(&gif(4, 4, 0, 0, 65535, 65535)).is_err());
+	}
+}
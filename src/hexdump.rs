@@ -9,7 +9,7 @@ pub fn dump(prefix_spaces: usize, data: &[u8], limit: usize) -> String {
         for j in 0..16 {
             if i + j < limit { line.push_str(&format!("{:02x} ", data[i + j])); } else { line.push_str("   "); }
         }
-        line.push_str(" ");
+        line.push(' ');
         for j in 0..16 {
             if i + j < limit {
                 let c = data[i + j];
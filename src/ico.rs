@@ -0,0 +1,124 @@
+// ICO/CUR 目录解析，以及把图标内嵌的 DIB 还原成完整 BMP 文件
+use anyhow::{bail, Context, Result};
+
+// 各图标的数据：PNG 文件或不带文件头的 DIB（高度为 XOR + AND 两部分之和）
+pub fn parse(data: &[u8]) -> Result<Vec<Vec<u8>>> {
+	if data.len() < 6 || data[0..2] != [0, 0] || !matches!(data[2..4], [1, 0] | [2, 0]) {
+		bail!("Not an ICO/CUR file");
+	}
+	let count = u16::from_le_bytes([data[4], data[5]]) as usize;
+	let mut out = Vec::new();
+	for i in 0..count {
+		let e = data.get(6 + i * 16..6 + i * 16 + 16).context("truncated ICO directory")?;
+		let size = u32::from_le_bytes([e[8], e[9], e[10], e[11]]) as usize;
+		let offset = u32::from_le_bytes([e[12], e[13], e[14], e[15]]) as usize;
+		let Some(d) = data.get(offset..offset.saturating_add(size)) else { continue; };
+		out.push(d.to_vec());
+	}
+	Ok(out)
+}
+
+// 拆分后的 DIB：补上文件头的 BMP（仅 XOR 部分）与 AND 掩码（自下而上，每行 4 字节对齐）
+pub struct Dib {
+	pub bmp: Vec<u8>,
+	pub mask_stride: usize,
+	pub mask: Vec<u8>,
+	pub width: usize,
+	pub height: usize,
+}
+
+pub fn split_dib(dib: &[u8]) -> Result<Dib> {
+	let hdr = dib.get(..40).context("truncated DIB header")?;
+	let rd32 = |p: usize| u32::from_le_bytes([hdr[p], hdr[p + 1], hdr[p + 2], hdr[p + 3]]);
+	let header_size = rd32(0) as usize;
+	let width = (rd32(4) as i32).unsigned_abs() as usize;
+	let height = (rd32(8) as i32).unsigned_abs() as usize / 2;
+	let bpp = u16::from_le_bytes([hdr[14], hdr[15]]) as usize;
+	let compression = rd32(16);
+	let colors = match rd32(32) as usize {
+		0 if bpp <= 8 => 1 << bpp,
+		n => n,
+	};
+	let masks = if compression == 3 && header_size == 40 { 12 } else { 0 };
+	let pixel_offset = colors.checked_mul(4).and_then(|c| c.checked_add(header_size + masks));
+	let xor_size = width.checked_mul(bpp).and_then(|b| (b.div_ceil(32) * 4).checked_mul(height));
+	// 文件头中的大小与偏移为 32 位
+	let (Some(pixel_offset), Some(xor_size)) = (pixel_offset, xor_size) else { bail!("bad DIB size {}x{}x{}", width, height, bpp); };
+	let Some(file_size) = pixel_offset.checked_add(xor_size).and_then(|n| n.checked_add(14)).and_then(|n| u32::try_from(n).ok()) else {
+		bail!("bad DIB size {}x{}x{}", width, height, bpp);
+	};
+	let mask_stride = width.div_ceil(32) * 4;
+
+	let mut bmp = Vec::with_capacity(14 + dib.len());
+	bmp.extend_from_slice(b"BM");
+	bmp.extend_from_slice(&file_size.to_le_bytes());
+	bmp.extend_from_slice(&[0; 4]);
+	bmp.extend_from_slice(&(14 + pixel_offset as u32).to_le_bytes());
+	bmp.extend_from_slice(dib.get(..pixel_offset + xor_size).context("truncated DIB pixel data")?);
+	// 高度改为 XOR 部分的高度
+	bmp[22..26].copy_from_slice(&(height as i32).to_le_bytes());
+
+	let mask = dib.get(pixel_offset + xor_size..).unwrap_or_default();
+	let mask = mask[..mask.len().min(mask_stride.saturating_mul(height))].to_vec();
+	Ok(Dib { bmp, mask_stride, mask, width, height })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// 2x2、32 位的 DIB，AND 掩码每行 4 字节
+	fn dib(width: u32, height: u32, bpp: u16) -> Vec<u8> {
+		let mut d = Vec::new();
+		d.extend_from_slice(&40u32.to_le_bytes());
+		d.extend_from_slice(&width.to_le_bytes());
+		d.extend_from_slice(&(height * 2).to_le_bytes());
+		d.extend_from_slice(&1u16.to_le_bytes());
+		d.extend_from_slice(&bpp.to_le_bytes());
+		d.extend_from_slice(&[0; 24]);
+		d
+	}
+
+	#[test]
+	fn directory_entries() {
+		let mut ico = vec![0, 0, 1, 0, 3, 0];
+		// PNG、DIB、超出文件范围的条目
+		for (size, offset) in [(8u32, 54u32), (4, 62), (16, 60)] {
+			ico.extend_from_slice(&[16, 16, 0, 0, 1, 0, 32, 0]);
+			ico.extend_from_slice(&size.to_le_bytes());
+			ico.extend_from_slice(&offset.to_le_bytes());
+		}
+		ico.extend_from_slice(b"\x89PNG\r\n\x1a\n");
+		ico.extend_from_slice(b"DIB!");
+		assert_eq!(parse(&ico).unwrap(), [b"\x89PNG\r\n\x1a\n".to_vec(), b"DIB!".to_vec()]);
+		assert!(parse(b"\x00\x00\x03\x00\x01\x00").is_err());
+		assert!(parse(&ico[..20]).is_err());
+	}
+
+	#[test]
+	fn split_xor_and_mask() {
+		let mut d = dib(2, 2, 32);
+		d.extend((0..16).map(|i| i as u8));
+		d.extend_from_slice(&[0x80, 0, 0, 0, 0x40, 0, 0, 0]);
+		let dib = split_dib(&d).unwrap();
+		assert_eq!((dib.width, dib.height, dib.mask_stride), (2, 2, 4));
+		assert_eq!(dib.mask, [0x80, 0, 0, 0, 0x40, 0, 0, 0]);
+		assert_eq!(&dib.bmp[..2], b"BM");
+		assert_eq!(u32::from_le_bytes(dib.bmp[2..6].try_into().unwrap()), 14 + 40 + 16);
+		assert_eq!(u32::from_le_bytes(dib.bmp[10..14].try_into().unwrap()), 14 + 40);
+		assert_eq!(i32::from_le_bytes(dib.bmp[22..26].try_into().unwrap()), 2);
+		assert_eq!(&dib.bmp[54..], (0..16).collect::<Vec<u8>>());
+	}
+
+	#[test]
+	fn oversized_dib() {
+		// XOR 部分的大小溢出，或超过 4 GiB 使文件头的大小字段放不下
+		assert!(split_dib(&dib(0x7fff_ffff, 0x4000_0000, 0xffff)).is_err());
+		assert!(split_dib(&dib(0x7fff_ffff, 0x4000_0000, 32)).is_err());
+		assert!(split_dib(&dib(0x10000, 0x10000, 32)).is_err());
+		// 调色板项数过大
+		let mut d = dib(1, 1, 8);
+		d[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+		assert!(split_dib(&d).is_err());
+	}
+}
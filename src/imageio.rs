@@ -1,18 +1,119 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::Read;
 use std::path::Path;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ImgFormat { Png, Bmp, Unknown }
+pub enum ImgFormat { Png, Bmp, Gif, Pnm, Tiff, WebP, Tga, Qoi, Jpeg, Wav, Au, Archive }
 
 #[derive(Debug, Clone)]
 pub struct PngChunk {
     pub ty: [u8;4],
     pub data: Vec<u8>,
+    pub crc: u32, // 文件中存储的 CRC
 }
 
+impl PngChunk {
+    // 按规范对类型 + 数据计算的 CRC
+    pub fn computed_crc(&self) -> u32 {
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(&self.ty);
+        crc.update(&self.data);
+        crc.finalize()
+    }
+
+    pub fn crc_ok(&self) -> bool { self.crc == self.computed_crc() }
+
+    // 类型首字母小写为辅助块，第二个字母小写为私有块
+    pub fn is_critical(&self) -> bool { self.ty[0].is_ascii_uppercase() }
+    pub fn is_private(&self) -> bool { self.ty[1].is_ascii_lowercase() }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ihdr {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: u8,
+    pub interlace: u8,
+}
+
+impl Ihdr {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 13 { return None; }
+        Some(Self {
+            width: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+            height: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            bit_depth: data[8],
+            color_type: data[9],
+            interlace: data[12],
+        })
+    }
+
+    pub fn channels(&self) -> usize {
+        match self.color_type { 0 | 3 => 1, 4 => 2, 2 => 3, 6 => 4, _ => 1 }
+    }
+
+    // 每行字节数（不含 filter 字节）
+    pub fn stride(&self) -> usize {
+        (self.width as usize * self.channels() * self.bit_depth as usize).div_ceil(8)
+    }
+
+    // 解压后 IDAT 数据的应有长度（含 filter 字节，隔行时为 7 个 pass 之和）
+    pub fn imagedata_len(&self) -> usize {
+        self.rows().last().map_or(0, |&(start, len)| start + len)
+    }
+
+    // 每条扫描线在解压后 IDAT 数据中的 (偏移, 长度)，长度含 filter 字节；隔行时各 pass 的行宽不同
+    pub fn rows(&self) -> Vec<(usize, usize)> {
+        let line = |w: usize| (w * self.channels() * self.bit_depth as usize).div_ceil(8) + 1;
+        let (w, h) = (self.width as usize, self.height as usize);
+        let passes = if self.interlace == 0 { vec![(w, h)] } else {
+            ADAM7.iter().map(|&(x0, y0, dx, dy)| (w.saturating_sub(x0).div_ceil(dx), h.saturating_sub(y0).div_ceil(dy))).collect()
+        };
+        let mut out = Vec::new();
+        let mut pos = 0;
+        for (pw, ph) in passes {
+            if pw == 0 { continue; }
+            for _ in 0..ph {
+                out.push((pos, line(pw)));
+                pos += line(pw);
+            }
+        }
+        out
+    }
+
+    // filter 计算时使用的像素字节数（至少 1）
+    pub fn bpp(&self) -> usize {
+        (self.channels() * self.bit_depth as usize).div_ceil(8).max(1)
+    }
+}
+
+// 文本元数据（PNG tEXt/zTXt/iTXt 等），值保留原始字节
+#[derive(Debug, Clone)]
+pub struct MetaEntry {
+    pub key: String,
+    pub value: Vec<u8>,
+    pub chunk: Option<usize>,       // 来源块索引
+    pub lang: Option<String>,       // iTXt 语言标签
+    pub translated: Option<Vec<u8>>, // iTXt 翻译后的关键字
+}
+
+impl MetaEntry {
+    pub fn new(key: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        Self { key: key.into(), value: value.into(), chunk: None, lang: None, translated: None }
+    }
+}
+
+// Adam7 各 pass 的 (x 起点, y 起点, x 步长, y 步长)
+pub const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2),
+];
+
+// 从内存数据加载某一格式
+type Loader = fn(&[u8]) -> Result<Image>;
+
 #[derive(Debug, Clone)]
 pub struct Image {
     pub format: ImgFormat,
@@ -20,43 +121,139 @@ pub struct Image {
     pub height: u32,
     pub pixels: Vec<u8>, // RGBA8 (decoded pixels)
     pub imagedata: Vec<u8>, // raw scanlines with filter bytes (PNG) or pixels (BMP)
-    pub metadata: HashMap<String, String>,
+    pub metadata: Vec<MetaEntry>, // 按出现顺序，重复的关键字各自保留
     pub chunks: Vec<PngChunk>, // PNG only; others empty
     pub extradata: Vec<Vec<u8>>, // 占位
+    pub recovered_size: Option<(u32, u32)>, // 按 IHDR CRC 爆破出的宽高（PNG）
+    pub subimages: Vec<(String, Image)>, // 子图像（APNG 帧、帧差等），各自单独扫描
+    pub jpeg: Option<crate::jpeg::Jpeg>, // JPEG 标记段结构
+    pub audio: Option<crate::audio::Audio>, // 音频：pixels 为各样本的最低字节，每帧一个"像素"
+    pub adam7_pass: bool, // Adam7 pass 子图像：扫描线已包含在主图像的 imagedata 中
+    pub warnings: Vec<String>, // 加载时跳过的结构（如无法解码的 TIFF 页），由 checker 输出
 }
 
 impl Image {
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path_ref = path.as_ref();
+        let buf = std::fs::read(path_ref).with_context(|| format!("open {:?}", path_ref))?;
         let ext = path_ref.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
-        match ext.as_str() {
-            "png" => Self::load_png(path_ref),
-            "bmp" => Self::load_bmp(path_ref),
-            _ => Self::load_auto(path_ref),
+        Self::load_with_ext(&ext, &buf)
+    }
+
+    // 先按扩展名加载；扩展名与内容不符（如实为 PNG 的 .jpg）时按魔数重试，仍失败则报告按扩展名加载的错误
+    fn load_with_ext(ext: &str, buf: &[u8]) -> Result<Self> {
+        let Some(load) = Self::loader_for_ext(ext) else { return Self::from_bytes(buf); };
+        load(buf).or_else(|e| match Self::sniff(buf) {
+            Some(other) if !std::ptr::fn_addr_eq(other, load) => other(buf).map_err(|_| e),
+            _ => Err(e),
+        })
+    }
+
+    // 扩展名（小写）是否为支持的格式，批量扫描目录时用于筛选
+    pub fn is_supported_ext(ext: &str) -> bool {
+        Self::loader_for_ext(ext).is_some()
+    }
+
+    fn loader_for_ext(ext: &str) -> Option<Loader> {
+        Some(match ext {
+            "png" => Self::load_png_bytes,
+            "bmp" => Self::load_bmp_bytes,
+            "gif" => Self::load_gif_bytes,
+            "pbm" | "pgm" | "ppm" | "pnm" | "pam" => Self::load_pnm_bytes,
+            "tif" | "tiff" => Self::load_tiff_bytes,
+            "webp" => Self::load_webp_bytes,
+            "ico" | "cur" => Self::load_ico_bytes,
+            "tga" => Self::load_tga_bytes,
+            "qoi" => Self::load_qoi_bytes,
+            "jpg" | "jpeg" | "jfif" => Self::load_jpeg_bytes,
+            "wav" => Self::load_wav_bytes,
+            "au" | "snd" => Self::load_au_bytes,
+            "zip" | "7z" | "tar" | "gz" | "tgz" => Self::load_archive_bytes,
+            _ => return None,
+        })
+    }
+
+    // 按魔数识别格式（用于 stdin、压缩包成员等没有扩展名的数据），都不匹配时尝试 TGA、BMP
+    pub fn from_bytes(buf: &[u8]) -> Result<Self> {
+        if let Some(load) = Self::sniff(buf) {
+            return load(buf);
+        }
+        if !buf.starts_with(b"BM") && crate::tga::looks_like_tga(buf) {
+            return Self::load_tga_bytes(buf);
+        }
+        Self::load_bmp_bytes(buf)
+    }
+
+    // 只认有明确魔数的格式
+    fn sniff(buf: &[u8]) -> Option<Loader> {
+        if buf.starts_with(b"\x89PNG\r\n\x1a\n") {
+            return Some(Self::load_png_bytes);
+        }
+        if buf.starts_with(b"GIF8") {
+            return Some(Self::load_gif_bytes);
+        }
+        if buf.len() >= 3 && buf[0] == b'P' && (b'1'..=b'7').contains(&buf[1]) && buf[2].is_ascii_whitespace() {
+            return Some(Self::load_pnm_bytes);
+        }
+        if buf.starts_with(b"II*\0") || buf.starts_with(b"MM\0*") {
+            return Some(Self::load_tiff_bytes);
+        }
+        if buf.starts_with(b"RIFF") {
+            // RIFF 形式类型在第 8..12 字节
+            return Some(if buf.get(8..12) == Some(b"WAVE") { Self::load_wav_bytes } else { Self::load_webp_bytes });
         }
+        if buf.starts_with(b".snd") {
+            return Some(Self::load_au_bytes);
+        }
+        if buf.len() >= 6 && (buf.starts_with(&[0, 0, 1, 0]) || buf.starts_with(&[0, 0, 2, 0])) && buf[4..6] != [0, 0] {
+            return Some(Self::load_ico_bytes);
+        }
+        if buf.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            return Some(Self::load_jpeg_bytes);
+        }
+        if buf.starts_with(b"qoif") {
+            return Some(Self::load_qoi_bytes);
+        }
+        if buf.starts_with(b"BM") {
+            return Some(Self::load_bmp_bytes);
+        }
+        if crate::archive::is_archive(buf) {
+            return Some(Self::load_archive_bytes);
+        }
+        None
     }
 
-    fn load_auto(path: &Path) -> Result<Self> {
-        // 简易魔数检测 PNG，否则尝试 BMP
-        let mut f = File::open(path).with_context(|| format!("open {:?}", path))?;
-        let mut sig = [0u8; 8];
-        let n = f.read(&mut sig)?;
-        drop(f);
-        if n >= 8 && &sig == b"\x89PNG\r\n\x1a\n" {
-            return Self::load_png(path);
+    // 压缩包：每个可识别的图像/音频成员作为以成员路径命名的子图像，嵌套的压缩包不再展开
+    fn load_archive_bytes(buf: &[u8]) -> Result<Self> {
+        let mut img = Image::from_rgba(ImgFormat::Archive, 0, 0, Vec::new());
+        for (name, data) in crate::archive::members(buf)? {
+            let ext = Path::new(&name).extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+            if crate::archive::is_archive(&data) {
+                continue;
+            }
+            if Self::loader_for_ext(&ext).is_none() && Self::sniff(&data).is_none() {
+                continue;
+            }
+            match Self::load_with_ext(&ext, &data) {
+                Ok(member) => img.subimages.push((name, member)),
+                Err(e) => eprintln!("{} {}: {:#}", "[!]".red(), name, e),
+            }
         }
-        Self::load_bmp(path)
+        if img.subimages.is_empty() {
+            bail!("no supported images in archive");
+        }
+        Ok(img)
     }
 
-    fn load_bmp(path: &Path) -> Result<Self> {
-        let img = image::open(path).with_context(|| "decode bmp")?;
+    fn load_bmp_bytes(buf: &[u8]) -> Result<Self> {
+        let img = image::load_from_memory_with_format(buf, image::ImageFormat::Bmp).with_context(|| "decode bmp")?;
         let rgba = img.to_rgba8();
         let (w, h) = rgba.dimensions();
         let pixels = rgba.to_vec();
         
         // 对于 BMP，imagedata 需要是原始的像素数据（BGR格式，带行填充）
         // 手动读取BMP文件的像素数据部分
-        let imagedata = Self::read_bmp_pixel_data(path, w, h)?;
+        let imagedata = Self::read_bmp_pixel_data(buf, w, h)?;
         
         Ok(Self {
             format: ImgFormat::Bmp,
@@ -64,19 +261,21 @@ impl Image {
             height: h,
             imagedata,
             pixels,
-            metadata: HashMap::new(),
+            metadata: Vec::new(),
             chunks: Vec::new(),
             extradata: Vec::new(),
+            recovered_size: None,
+            subimages: Vec::new(),
+            jpeg: None,
+            audio: None,
+            adam7_pass: false,
+            warnings: Vec::new(),
         })
     }
     
-    fn read_bmp_pixel_data(path: &Path, width: u32, height: u32) -> Result<Vec<u8>> {
-        use std::io::{Read, Seek, SeekFrom};
-        let mut f = File::open(path)?;
-        
+    fn read_bmp_pixel_data(buf: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
         // 读取 BMP 头部
-        let mut header = [0u8; 14];
-        f.read_exact(&mut header)?;
+        let header = buf.get(0..14).context("truncated BMP header")?;
         
         // 检查签名
         if &header[0..2] != b"BM" {
@@ -84,28 +283,22 @@ impl Image {
         }
         
         // 读取 pixel data 的偏移量（字节10-13）
-        let pixel_offset = u32::from_le_bytes([header[10], header[11], header[12], header[13]]);
+        let pixel_offset = u32::from_le_bytes([header[10], header[11], header[12], header[13]]) as usize;
         
         // 读取 DIB 头部
-        let mut dib_header = [0u8; 40];
-        f.read_exact(&mut dib_header)?;
+        let dib_header = buf.get(14..54).context("truncated DIB header")?;
         
         let bits_per_pixel = u16::from_le_bytes([dib_header[14], dib_header[15]]);
         // height 可能是负数，表示 top-down
         let height_signed = i32::from_le_bytes([dib_header[8], dib_header[9], dib_header[10], dib_header[11]]);
         let is_bottom_up = height_signed > 0;
         
-        // 跳转到像素数据
-        f.seek(SeekFrom::Start(pixel_offset as u64))?;
-        
         // 计算每行字节数（需要4字节对齐）
-        let bytes_per_pixel = (bits_per_pixel / 8) as usize;
-        let row_size = ((width as usize * bytes_per_pixel + 3) / 4) * 4;
+        let row_size = (width as usize * bits_per_pixel as usize).div_ceil(32) * 4;
         let total_size = row_size * height as usize;
         
         // 读取所有像素数据
-        let mut pixel_data = vec![0u8; total_size];
-        f.read_exact(&mut pixel_data)?;
+        let mut pixel_data = buf.get(pixel_offset..pixel_offset + total_size).context("truncated BMP pixel data")?.to_vec();
         
         // BMP 默认是 bottom-up 存储，需要翻转到 top-down
         // 因为 image crate 解码后的 RGBA 是 top-down 的
@@ -122,9 +315,247 @@ impl Image {
         Ok(pixel_data)
     }
 
-    fn load_png(path: &Path) -> Result<Self> {
-        let file = File::open(path).with_context(|| format!("open {:?}", path))?;
-        let mut r = BufReader::new(file);
+    // GIF：首帧作为主图像，其余帧与帧差作为子图像；imagedata 为画布大小的调色板索引，
+    // 调色板与扩展块放入 metadata，结束符后的数据放入 extradata
+    fn load_gif_bytes(buf: &[u8]) -> Result<Self> {
+        use image::AnimationDecoder;
+        let gif = crate::gif::parse(buf)?;
+        let frames: Vec<image::RgbaImage> = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(buf))
+            .and_then(|d| d.into_frames().collect_frames())
+            .with_context(|| "decode gif")?
+            .into_iter()
+            .map(|f| f.into_buffer())
+            .collect();
+        let (w, h) = (gif.width as u32, gif.height as u32);
+
+        let mut images: Vec<Image> = frames.iter().enumerate().map(|(i, frame)| {
+            let mut img = Image::from_rgba(ImgFormat::Gif, w, h, frame.to_vec());
+            if let Some(f) = gif.frames.get(i) {
+                img.imagedata = gif.canvas_indices(f);
+                if let Some(p) = &f.palette {
+                    img.metadata.push(MetaEntry::new("GIF local palette", p.clone()));
+                }
+            }
+            img
+        }).collect();
+        if images.is_empty() {
+            return Err(anyhow::anyhow!("GIF has no frames"));
+        }
+        let diffs = frame_diffs(ImgFormat::Gif, &frames);
+        let mut img = images.remove(0);
+        if let Some(p) = &gif.palette {
+            img.metadata.insert(0, MetaEntry::new("GIF global palette", p.clone()));
+        }
+        img.metadata.extend(gif.extensions.iter().map(|(k, v)| MetaEntry::new(format!("GIF {}", k), v.clone())));
+        if !gif.trailer.is_empty() {
+            img.extradata.push(gif.trailer);
+        }
+        img.subimages = images.into_iter().enumerate().map(|(i, f)| (format!("frame:{}", i + 1), f)).collect();
+        img.subimages.extend(diffs);
+        Ok(img)
+    }
+
+    // PNM/PAM：imagedata 为原始栅格（ASCII 格式转换为样本字节），头部注释放入 metadata，
+    // 栅格之后的数据放入 extradata
+    fn load_pnm_bytes(buf: &[u8]) -> Result<Self> {
+        let pnm = crate::pnm::parse(buf)?;
+        let mut img = Image::from_rgba(ImgFormat::Pnm, pnm.width as u32, pnm.height as u32, pnm.to_rgba());
+        img.metadata = pnm.comments.iter().map(|c| MetaEntry::new("PNM comment", c.clone())).collect();
+        if !pnm.tupltype.is_empty() {
+            img.metadata.push(MetaEntry::new("PAM TUPLTYPE", pnm.tupltype.as_bytes()));
+        }
+        img.imagedata = pnm.raster;
+        if !pnm.trailer.is_empty() {
+            img.extradata.push(pnm.trailer);
+        }
+        Ok(img)
+    }
+
+    // TIFF：第一个 IFD 为主图像，其余页作为子图像；imagedata 为解压后的条带数据，
+    // ASCII 标签放入 metadata，未被引用的字节区间放入 extradata
+    fn load_tiff_bytes(buf: &[u8]) -> Result<Self> {
+        let tiff = crate::tiff::parse(buf)?;
+        let mut pages = tiff.pages.into_iter().map(|p| {
+            let mut img = Image::from_rgba(ImgFormat::Tiff, p.width as u32, p.height as u32, p.rgba);
+            img.imagedata = p.samples;
+            img.metadata = p.tags.into_iter().map(|(k, v)| MetaEntry::new(format!("TIFF {}", k), v)).collect();
+            img
+        });
+        let mut img = pages.next().context("no TIFF pages")?;
+        img.subimages = pages.enumerate().map(|(i, p)| (format!("page:{}", i + 1), p)).collect();
+        img.extradata = tiff.unused.into_iter().map(|(_, data)| data).collect();
+        img.warnings = tiff.warnings;
+        Ok(img)
+    }
+
+    // WebP：像素由 image crate 解码（VP8L 无损时 LSB 可用），RIFF 中的 EXIF/XMP/ICCP 及未知块放入 metadata，
+    // RIFF 声明大小之后的数据放入 extradata
+    fn load_webp_bytes(buf: &[u8]) -> Result<Self> {
+        let riff = crate::webp::parse(buf)?;
+        let rgba = image::load_from_memory_with_format(buf, image::ImageFormat::WebP).with_context(|| "decode webp")?.to_rgba8();
+        let mut img = Image::from_rgba(ImgFormat::WebP, rgba.width(), rgba.height(), rgba.into_raw());
+        for (ty, data) in &riff.chunks {
+            match ty {
+                b"EXIF" => {
+                    let tags = crate::exif::parse(data);
+                    if tags.is_empty() {
+                        img.metadata.push(MetaEntry::new("WebP EXIF", data.clone()));
+                    }
+                    img.metadata.extend(tags.into_iter().map(|(k, v)| MetaEntry::new(format!("WebP EXIF {}", k), v)));
+                }
+                b"XMP " => img.metadata.push(MetaEntry::new("WebP XMP", data.clone())),
+                b"ICCP" => img.metadata.extend(parse_icc_profile(data, "WebP ICCP")),
+                b"VP8 " | b"VP8L" | b"VP8X" | b"ALPH" | b"ANIM" | b"ANMF" => {}
+                _ => img.metadata.push(MetaEntry::new(format!("WebP chunk {}", String::from_utf8_lossy(ty)), data.clone())),
+            }
+        }
+        if !riff.trailer.is_empty() {
+            img.extradata.push(riff.trailer);
+        }
+        Ok(img)
+    }
+
+    fn load_wav_bytes(buf: &[u8]) -> Result<Self> {
+        Ok(Self::from_audio(ImgFormat::Wav, crate::audio::parse_wav(buf)?))
+    }
+
+    fn load_au_bytes(buf: &[u8]) -> Result<Self> {
+        Ok(Self::from_audio(ImgFormat::Au, crate::audio::parse_au(buf)?))
+    }
+
+    // 音频作为一行图像：每帧一个像素，各声道样本的最低字节依次排列；imagedata 为原始 PCM 字节
+    fn from_audio(format: ImgFormat, audio: crate::audio::Audio) -> Self {
+        let mut img = Image::from_rgba(format, audio.frames() as u32, 1, audio.low_bytes());
+        img.imagedata = audio.data.clone();
+        let prefix = if format == ImgFormat::Wav { "WAV" } else { "AU" };
+        img.metadata.extend(audio.fields.iter().map(|(k, v)| MetaEntry::new(format!("{} {}", prefix, k), v.clone())));
+        if !audio.trailer.is_empty() {
+            img.extradata.push(audio.trailer.clone());
+        }
+        img.audio = Some(audio);
+        img
+    }
+
+    // ICO/CUR：第一个图标为主图像，其余作为子图像 icon:N；
+    // BMP 图标的 AND 掩码作为子图像 mask（每位一个像素，值为 0/1，imagedata 为掩码原始行）
+    fn load_ico_bytes(buf: &[u8]) -> Result<Self> {
+        let mut icons = Vec::new();
+        for data in crate::ico::parse(buf)? {
+            if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+                icons.push(Self::load_png_bytes(&data)?);
+                continue;
+            }
+            let dib = crate::ico::split_dib(&data)?;
+            let mut img = Self::load_bmp_bytes(&dib.bmp)?;
+            let mut rows: Vec<&[u8]> = dib.mask.chunks(dib.mask_stride.max(1)).collect();
+            rows.reverse();
+            let pixels = (0..dib.height)
+                .flat_map(|y| (0..dib.width).map(move |x| (y, x)))
+                .flat_map(|(y, x)| {
+                    let bit = rows.get(y).and_then(|r| r.get(x / 8)).map_or(0, |b| (b >> (7 - x % 8)) & 1);
+                    [bit, bit, bit, 0xff]
+                })
+                .collect();
+            let mut mask = Image::from_rgba(ImgFormat::Bmp, dib.width as u32, dib.height as u32, pixels);
+            mask.imagedata = rows.concat();
+            img.subimages.insert(0, ("mask".to_string(), mask));
+            icons.push(img);
+        }
+        if icons.is_empty() {
+            return Err(anyhow::anyhow!("ICO has no images"));
+        }
+        let mut img = icons.remove(0);
+        img.subimages.extend(icons.into_iter().enumerate().map(|(i, icon)| (format!("icon:{}", i + 1), icon)));
+        Ok(img)
+    }
+
+    // TGA：像素由 image crate 解码，imagedata 为自上而下的原始像素字节（RLE 已解压），
+    // 图像 ID、扩展区与开发者区字段放入 metadata，未引用的字节放入 extradata
+    fn load_tga_bytes(buf: &[u8]) -> Result<Self> {
+        let tga = crate::tga::parse(buf)?;
+        let rgba = image::load_from_memory_with_format(buf, image::ImageFormat::Tga).with_context(|| "decode tga")?.to_rgba8();
+        let mut img = Image::from_rgba(ImgFormat::Tga, tga.width as u32, tga.height as u32, rgba.into_raw());
+        img.imagedata = tga.imagedata;
+        img.metadata = tga.fields.into_iter().map(|(k, v)| MetaEntry::new(format!("TGA {}", k), v)).collect();
+        img.extradata = tga.unused.into_iter().map(|(_, data)| data).collect();
+        Ok(img)
+    }
+
+    // QOI：imagedata 为解码后的原始像素（按头部声明的 3 或 4 通道），结束标记之后的数据放入 extradata
+    fn load_qoi_bytes(buf: &[u8]) -> Result<Self> {
+        let qoi = crate::qoi::parse(buf)?;
+        let mut img = Image::from_rgba(ImgFormat::Qoi, qoi.width as u32, qoi.height as u32, qoi.rgba);
+        img.imagedata = if qoi.channels == 3 {
+            img.pixels.chunks(4).flat_map(|p| &p[..3]).copied().collect()
+        } else {
+            img.pixels.clone()
+        };
+        if !qoi.trailer.is_empty() {
+            img.extradata.push(qoi.trailer);
+        }
+        Ok(img)
+    }
+
+    // JPEG：只分析容器结构，不解码像素（pixels 为空，跳过像素 LSB 扫描）；
+    // COM 与 APPn 载荷放入 metadata，EOI 之后的数据放入 extradata，缩略图作为子图像 thumb:N
+    fn load_jpeg_bytes(buf: &[u8]) -> Result<Self> {
+        let jpeg = crate::jpeg::parse(buf)?;
+        let mut img = Image::from_rgba(ImgFormat::Jpeg, jpeg.width, jpeg.height, Vec::new());
+        let mut thumbs = Vec::new();
+        let mut icc = Vec::new();
+        for seg in &jpeg.segments {
+            let name = crate::jpeg::marker_name(seg.marker);
+            if !seg.garbage.is_empty() {
+                img.metadata.push(MetaEntry::new(format!("JPEG data before {}", name), seg.garbage.clone()));
+            }
+            let d = &seg.data[..];
+            match seg.marker {
+                0xFE => img.metadata.push(MetaEntry::new("JPEG COM", d)),
+                0xE0 if d.starts_with(b"JFIF\0") => {}
+                // JFXX 扩展：0x10 为 JPEG 编码的缩略图
+                0xE0 if d.starts_with(b"JFXX\0") && d.get(5) == Some(&0x10) => thumbs.push(d[6..].to_vec()),
+                0xE1 if d.starts_with(b"Exif\0\0") => {
+                    for (k, v) in crate::exif::parse(d) {
+                        if k == "Thumbnail" { thumbs.push(v.clone()); }
+                        img.metadata.push(MetaEntry::new(format!("JPEG EXIF {}", k), v));
+                    }
+                }
+                0xE1 if d.starts_with(b"http://ns.adobe.com/xap/1.0/\0") => {
+                    img.metadata.push(MetaEntry::new("JPEG XMP", &d[29..]));
+                }
+                // 扩展 XMP：GUID(32) + 总长(4) + 偏移(4) 之后为数据片段
+                0xE1 if d.starts_with(b"http://ns.adobe.com/xmp/extension/\0") => {
+                    img.metadata.push(MetaEntry::new("JPEG XMP extension", d.get(75..).unwrap_or_default()));
+                }
+                // ICC 配置文件可能分成多段：序号(1) + 总段数(1)
+                0xE2 if d.starts_with(b"ICC_PROFILE\0") => icc.extend_from_slice(d.get(14..).unwrap_or_default()),
+                0xED if d.starts_with(b"Photoshop 3.0\0") => {
+                    img.metadata.extend(parse_photoshop(&d[14..], &mut thumbs));
+                }
+                0xEE if d.starts_with(b"Adobe") => {}
+                0xE0..=0xEF => img.metadata.push(MetaEntry::new(format!("JPEG {}", name), d)),
+                m if !crate::jpeg::is_known(m) => img.metadata.push(MetaEntry::new(format!("JPEG {}", name), d)),
+                _ => {}
+            }
+        }
+        if !icc.is_empty() {
+            img.metadata.extend(parse_icc_profile(&icc, "JPEG ICC"));
+        }
+        if !jpeg.trailer.is_empty() {
+            img.extradata.push(jpeg.trailer.clone());
+        }
+        img.subimages = thumbs
+            .iter()
+            .filter_map(|t| Self::load_jpeg_bytes(t).ok())
+            .enumerate()
+            .map(|(i, t)| (format!("thumb:{}", i), t))
+            .collect();
+        img.jpeg = Some(jpeg);
+        Ok(img)
+    }
+
+    fn load_png_bytes(buf: &[u8]) -> Result<Self> {
+        let mut r = std::io::Cursor::new(buf);
 
         // 解析 PNG chunk 简单列表
         let mut sig = [0u8;8];
@@ -143,7 +574,7 @@ impl Image {
             if len > 0 { r.read_exact(&mut data)?; }
             let mut crc = [0u8;4];
             r.read_exact(&mut crc)?;
-            chunks.push(PngChunk { ty, data });
+            chunks.push(PngChunk { ty, data, crc: u32::from_be_bytes(crc) });
             if &ty == b"IEND" { 
                 found_iend = true;
                 break; 
@@ -160,27 +591,45 @@ impl Image {
             }
         }
 
-        // 用 image crate 解码像素
-        let img = image::open(path).with_context(|| "decode png")?;
+        // IHDR 的 CRC 不匹配时，尝试找出与存储的 CRC 相符的宽高（常见的隐藏部分行的手法）
+        let recovered_size = chunks.first()
+            .filter(|c| &c.ty == b"IHDR" && c.data.len() == 13 && !c.crc_ok())
+            .and_then(recover_ihdr_size);
+
+        // 用 image crate 解码像素；CRC 错误时解码器会拒绝，按修正后的 CRC 重新组装再解码
+        let decoded = if chunks.iter().all(|c| c.crc_ok()) {
+            image::load_from_memory_with_format(buf, image::ImageFormat::Png)
+        } else {
+            image::load_from_memory_with_format(&assemble_png(&chunks), image::ImageFormat::Png)
+        };
+        let img = match (decoded, recovered_size) {
+            (Ok(img), _) => img,
+            // 篡改后的宽度通常使扫描线错位而无法解码，此时直接使用恢复出的宽高
+            (Err(_), Some((w, h))) => {
+                let mut img = Self::load_png_resized(&chunks, &extradata, w, h)?;
+                img.recovered_size = recovered_size;
+                return Ok(img);
+            }
+            (Err(e), None) => return Err(e).with_context(|| "decode png"),
+        };
         let rgba = img.to_rgba8();
         let (w, h) = rgba.dimensions();
 
-        // 提取常见文本元数据（tEXt/iTXt/zTXt 简易）
-        let mut metadata = HashMap::new();
-        for ch in &chunks {
-            match &ch.ty {
-                b"tEXt" => {
-                    if let Some((k,v)) = parse_png_text(&ch.data) { metadata.insert(k, v); }
-                }
-                b"iTXt" => {
-                    if let Some((k,v)) = parse_png_itxt(&ch.data) { metadata.insert(k, v); }
-                }
-                b"zTXt" => {
-                    if let Some((k,v)) = parse_png_ztxt(&ch.data) { metadata.insert(k, v); }
-                }
-                _ => {}
-            }
-        }
+        // 文本及其他元数据块，按块顺序保留
+        let metadata: Vec<MetaEntry> = chunks.iter().enumerate().flat_map(|(idx, ch)| {
+            let entries = match &ch.ty {
+                b"tEXt" => parse_png_text(&ch.data).into_iter().collect(),
+                b"zTXt" => parse_png_ztxt(&ch.data).into_iter().collect(),
+                b"iTXt" => parse_png_itxt(&ch.data).into_iter().collect(),
+                b"eXIf" => crate::exif::parse(&ch.data).into_iter().map(|(k, v)| MetaEntry::new(format!("eXIf {}", k), v)).collect(),
+                b"iCCP" => parse_png_iccp(&ch.data),
+                b"tIME" => parse_png_time(&ch.data).into_iter().collect(),
+                b"sPLT" => parse_png_splt(&ch.data).into_iter().collect(),
+                b"hIST" => vec![MetaEntry::new("hIST", ch.data.clone())],
+                _ => Vec::new(),
+            };
+            entries.into_iter().map(move |e| MetaEntry { chunk: Some(idx), ..e })
+        }).collect();
 
         // 提取 imagedata：解压所有 IDAT 块得到原始扫描线数据
         let mut idat_data = Vec::new();
@@ -197,56 +646,736 @@ impl Image {
         let mut imagedata = Vec::new();
         decoder.read_to_end(&mut imagedata).with_context(|| "decompress IDAT")?;
 
-        Ok(Self {
+        // APNG：逐帧合成后的图像及相邻帧差
+        let subimages = if chunks.iter().any(|c| &c.ty == b"acTL") {
+            if chunks.iter().all(|c| c.crc_ok()) { apng_frames(buf, &chunks) } else { apng_frames(&assemble_png(&chunks), &chunks) }
+        } else {
+            Vec::new()
+        };
+
+        let mut img = Self {
             format: ImgFormat::Png,
             width: w,
             height: h,
             pixels: rgba.to_vec(),
             imagedata,
             metadata,
+            recovered_size,
             chunks,
             extradata,
-        })
+            subimages,
+            jpeg: None,
+            audio: None,
+            adam7_pass: false,
+            warnings: Vec::new(),
+        };
+        // Adam7：7 个 pass 各自作为独立图像扫描
+        img.subimages.extend(img.adam7_passes());
+        Ok(img)
+    }
+
+    // 每个 pass 拆成非隔行图像：imagedata 为该 pass 的扫描线，IHDR 改为 pass 的尺寸，
+    // 像素取自去隔行后图像中该 pass 覆盖的位置
+    fn adam7_passes(&self) -> Vec<(String, Image)> {
+        let Some(ihdr) = self.ihdr().filter(|h| h.interlace == 1) else { return Vec::new(); };
+        let Some(ihdr_chunk) = self.chunks.iter().find(|c| &c.ty == b"IHDR") else { return Vec::new(); };
+        let (w, h) = (ihdr.width as usize, ihdr.height as usize);
+        let mut out = Vec::new();
+        let mut offset = 0;
+        for (n, &(x0, y0, dx, dy)) in ADAM7.iter().enumerate() {
+            let pw = w.saturating_sub(x0).div_ceil(dx);
+            let ph = h.saturating_sub(y0).div_ceil(dy);
+            if pw == 0 || ph == 0 { continue; }
+            let pass_ihdr = Ihdr { width: pw as u32, height: ph as u32, interlace: 0, ..ihdr };
+            let len = ph * (pass_ihdr.stride() + 1);
+            let data = self.imagedata.get(offset..offset + len).unwrap_or_default().to_vec();
+            offset += len;
+
+            let mut pixels = Vec::with_capacity(pw * ph * 4);
+            for py in 0..ph {
+                for px in 0..pw {
+                    let i = ((y0 + py * dy) * w + x0 + px * dx) * 4;
+                    pixels.extend_from_slice(self.pixels.get(i..i + 4).unwrap_or(&[0; 4]));
+                }
+            }
+            let mut chunk = ihdr_chunk.clone();
+            chunk.data[0..4].copy_from_slice(&(pw as u32).to_be_bytes());
+            chunk.data[4..8].copy_from_slice(&(ph as u32).to_be_bytes());
+            chunk.data[12] = 0;
+            chunk.crc = chunk.computed_crc();
+            let mut img = Image::from_rgba(ImgFormat::Png, pw as u32, ph as u32, pixels);
+            img.imagedata = data;
+            img.chunks = vec![chunk];
+            img.adam7_pass = true;
+            out.push((format!("pass:{}", n + 1), img));
+        }
+        out
     }
+
+    // 只有像素的图像，其余字段为空
+    pub fn from_rgba(format: ImgFormat, width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        Self {
+            format,
+            width,
+            height,
+            pixels,
+            imagedata: Vec::new(),
+            metadata: Vec::new(),
+            chunks: Vec::new(),
+            extradata: Vec::new(),
+            recovered_size: None,
+            subimages: Vec::new(),
+            jpeg: None,
+            audio: None,
+            adam7_pass: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    // 用给定的宽高替换 IHDR 后重新解码（CRC 一并修正）
+    pub fn with_size(&self, width: u32, height: u32) -> Result<Self> {
+        Self::load_png_resized(&self.chunks, &self.extradata, width, height)
+    }
+
+    fn load_png_resized(chunks: &[PngChunk], extradata: &[Vec<u8>], width: u32, height: u32) -> Result<Self> {
+        let mut chunks = chunks.to_vec();
+        let ihdr = chunks.iter_mut().find(|c| &c.ty == b"IHDR").context("no IHDR chunk")?;
+        ihdr.data[0..4].copy_from_slice(&width.to_be_bytes());
+        ihdr.data[4..8].copy_from_slice(&height.to_be_bytes());
+        ihdr.crc = ihdr.computed_crc();
+        let mut img = Self::load_png_bytes(&assemble_png(&chunks))?;
+        img.extradata = extradata.to_vec();
+        Ok(img)
+    }
+}
+
+impl Image {
+    pub fn ihdr(&self) -> Option<Ihdr> {
+        self.chunks.iter().find(|c| &c.ty == b"IHDR").and_then(|c| Ihdr::parse(&c.data))
+    }
+
+    // 去除 PNG filter 后的逐行原始数据（原生布局，不含 filter 字节），仅支持非隔行
+    pub fn scanlines(&self) -> Option<Vec<Vec<u8>>> {
+        let ihdr = self.ihdr()?;
+        if ihdr.interlace != 0 { return None; }
+        unfilter(&self.imagedata, ihdr.stride(), ihdr.bpp(), ihdr.height as usize)
+    }
+
+    // 去除 filter 后的样本字节（按行拼接，不含 filter 字节）；BMP 等直接使用 imagedata，WebP 没有原始样本
+    pub fn rawpixels(&self) -> Option<Vec<u8>> {
+        match self.format {
+            ImgFormat::Png => self.scanlines().map(|rows| rows.concat()),
+            ImgFormat::Bmp | ImgFormat::Gif | ImgFormat::Pnm | ImgFormat::Tiff | ImgFormat::Tga | ImgFormat::Qoi | ImgFormat::Wav | ImgFormat::Au => Some(self.imagedata.clone()),
+            ImgFormat::WebP | ImgFormat::Jpeg | ImgFormat::Archive => None,
+        }
+    }
+
+    // IDAT 中 zlib 流结束之后的压缩数据
+    pub fn idat_trailer(&self) -> Vec<u8> {
+        use flate2::read::ZlibDecoder;
+        let idat: Vec<u8> = self.chunks.iter().filter(|c| &c.ty == b"IDAT").flat_map(|c| c.data.iter().copied()).collect();
+        let mut decoder = ZlibDecoder::new(&idat[..]);
+        if decoder.read_to_end(&mut Vec::new()).is_err() { return Vec::new(); }
+        idat.get(decoder.total_in() as usize..).unwrap_or_default().to_vec()
+    }
+
+    // 解压后超出 height * stride 的多余扫描线数据
+    pub fn imagedata_excess(&self) -> &[u8] {
+        match self.ihdr() {
+            Some(ihdr) if self.format == ImgFormat::Png => self.imagedata.get(ihdr.imagedata_len()..).unwrap_or_default(),
+            _ => &[],
+        }
+    }
+
+    // 每行扫描线开头的 filter 类型字节；隔行图像按 Adam7 各 pass 的行依次排列
+    pub fn filter_bytes(&self) -> Option<Vec<u8>> {
+        let ihdr = self.ihdr()?;
+        ihdr.rows().iter().map(|&(pos, _)| self.imagedata.get(pos).copied()).collect()
+    }
+}
+
+pub fn unfilter(data: &[u8], stride: usize, bpp: usize, height: usize) -> Option<Vec<Vec<u8>>> {
+    let mut rows: Vec<Vec<u8>> = Vec::with_capacity(height);
+    let mut prev = vec![0u8; stride];
+    for y in 0..height {
+        let start = y * (stride + 1);
+        if start + 1 + stride > data.len() { return None; }
+        let filter = data[start];
+        let mut line = data[start + 1..start + 1 + stride].to_vec();
+        for x in 0..stride {
+            let a = if x >= bpp { line[x - bpp] } else { 0 };
+            let b = prev[x];
+            let c = if x >= bpp { prev[x - bpp] } else { 0 };
+            let pred = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return None,
+            };
+            line[x] = line[x].wrapping_add(pred);
+        }
+        prev = line.clone();
+        rows.push(line);
+    }
+    Some(rows)
+}
+
+// APNG 帧按 image crate 的合成结果（完整画布）作为像素，
+// imagedata 为该帧自身的扫描线（IDAT 或 fdAT），附带按帧区域改写宽高的 IHDR 以便按字节遍历
+fn apng_frames(png: &[u8], chunks: &[PngChunk]) -> Vec<(String, Image)> {
+    use image::AnimationDecoder;
+    let Some(ihdr) = chunks.iter().find(|c| &c.ty == b"IHDR") else { return Vec::new(); };
+    let frames: Vec<image::RgbaImage> = match image::codecs::png::PngDecoder::new(std::io::Cursor::new(png)).and_then(|d| d.apng()) {
+        Ok(apng) => apng.into_frames().map_while(Result::ok).map(|f| f.into_buffer()).collect(),
+        Err(_) => return Vec::new(),
+    };
+
+    // fcTL 之后的 IDAT/fdAT 属于该帧；第一个 fcTL 之前的 IDAT 是不参与动画的默认图像
+    let mut raw: Vec<(PngChunk, Vec<u8>)> = Vec::new();
+    for ch in chunks {
+        match &ch.ty {
+            b"fcTL" if ch.data.len() >= 26 => {
+                let mut region = ihdr.clone();
+                region.data[0..8].copy_from_slice(&ch.data[4..12]);
+                region.crc = region.computed_crc();
+                raw.push((region, Vec::new()));
+            }
+            b"IDAT" => if let Some((_, data)) = raw.last_mut() { data.extend_from_slice(&ch.data); },
+            b"fdAT" if ch.data.len() > 4 => if let Some((_, data)) = raw.last_mut() { data.extend_from_slice(&ch.data[4..]); },
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    for (i, frame) in frames.iter().enumerate() {
+        let (w, h) = frame.dimensions();
+        let mut img = Image::from_rgba(ImgFormat::Png, w, h, frame.to_vec());
+        if let Some((region, data)) = raw.get(i) {
+            img.imagedata = inflate_or_raw(data);
+            img.chunks = vec![region.clone()];
+        }
+        out.push((format!("frame:{}", i), img));
+    }
+    out.extend(frame_diffs(ImgFormat::Png, &frames));
+    out
+}
+
+// 相邻帧 RGB 异或，差异处即为改动的位
+fn frame_diffs(format: ImgFormat, frames: &[image::RgbaImage]) -> Vec<(String, Image)> {
+    let mut out = Vec::new();
+    for i in 1..frames.len() {
+        let (a, b) = (&frames[i - 1], &frames[i]);
+        if a.dimensions() != b.dimensions() || a == b { continue; }
+        let pixels = a.iter().zip(b.iter()).enumerate()
+            .map(|(k, (x, y))| if k % 4 == 3 { 0xff } else { x ^ y })
+            .collect();
+        out.push((format!("diff:{}-{}", i - 1, i), Image::from_rgba(format, a.width(), a.height(), pixels)));
+    }
+    out
+}
+
+// Photoshop 图像资源块（8BIM）：IPTC 数据集逐项取出，缩略图（0x0409/0x040C）放入 thumbs
+fn parse_photoshop(data: &[u8], thumbs: &mut Vec<Vec<u8>>) -> Vec<MetaEntry> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while data.get(pos..pos + 4) == Some(b"8BIM") {
+        let Some(id) = data.get(pos + 4..pos + 6).map(|b| u16::from_be_bytes([b[0], b[1]])) else { break; };
+        // 名称为 Pascal 字符串，连同长度字节补齐到偶数
+        let name_len = data.get(pos + 6).map_or(0, |&n| n as usize + 1);
+        let p = pos + 6 + name_len + name_len % 2;
+        let Some(size) = data.get(p..p + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize) else { break; };
+        let res = data.get(p + 4..(p + 4).saturating_add(size)).unwrap_or_default();
+        match id {
+            0x0404 => out.extend(parse_iptc(res)),
+            0x0409 | 0x040C => thumbs.extend(res.get(28..).map(|t| t.to_vec())),
+            _ if !res.is_empty() => out.push(MetaEntry::new(format!("JPEG Photoshop 0x{:04X}", id), res)),
+            _ => {}
+        }
+        pos = p + 4 + size + size % 2;
+    }
+    out
+}
+
+// IPTC-IIM 数据集：0x1C 记录号 数据集号 长度(2) 值
+fn parse_iptc(data: &[u8]) -> Vec<MetaEntry> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while data.get(pos) == Some(&0x1C) && pos + 5 <= data.len() {
+        let (rec, ds) = (data[pos + 1], data[pos + 2]);
+        let len = u16::from_be_bytes([data[pos + 3], data[pos + 4]]) as usize;
+        if len & 0x8000 != 0 { break; }
+        let value = data.get(pos + 5..pos + 5 + len).unwrap_or_default();
+        let name = match (rec, ds) {
+            (2, 5) => "ObjectName".to_string(),
+            (2, 25) => "Keywords".to_string(),
+            (2, 80) => "By-line".to_string(),
+            (2, 105) => "Headline".to_string(),
+            (2, 116) => "CopyrightNotice".to_string(),
+            (2, 120) => "Caption".to_string(),
+            _ => format!("{}:{}", rec, ds),
+        };
+        if !value.is_empty() { out.push(MetaEntry::new(format!("JPEG IPTC {}", name), value)); }
+        pos += 5 + len;
+    }
+    out
+}
+
+// 合并已引用的字节区间后取补集，返回 (偏移, 数据)；1～3 字节的全零间隙视为对齐填充
+pub fn unused_ranges(data: &[u8], mut used: Vec<(usize, usize)>) -> Vec<(usize, Vec<u8>)> {
+    used.sort();
+    let mut out = Vec::new();
+    let mut pos = 0;
+    for (start, end) in used.into_iter().chain([(data.len(), data.len())]) {
+        let start = start.min(data.len());
+        if start > pos {
+            let gap = &data[pos..start];
+            if gap.len() >= 4 || gap.iter().any(|&b| b != 0) {
+                out.push((pos, gap.to_vec()));
+            }
+        }
+        pos = pos.max(end);
+    }
+    out
+}
+
+// 用给定的块重新组装 PNG 文件，CRC 重新计算
+fn assemble_png(chunks: &[PngChunk]) -> Vec<u8> {
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    for ch in chunks {
+        out.extend_from_slice(&(ch.data.len() as u32).to_be_bytes());
+        out.extend_from_slice(&ch.ty);
+        out.extend_from_slice(&ch.data);
+        out.extend_from_slice(&ch.computed_crc().to_be_bytes());
+    }
+    out
+}
+
+// 等长消息的 CRC 满足 crc(a^b^c) = crc(a)^crc(b)^crc(c)，因此
+// crc(w,h) = crc(w,0) ^ crc(0,h) ^ crc(0,0)，预先建立 crc(0,h) -> h 的表即可线性搜索
+// 先单独改高、再单独改宽，最后两者同时在 1..=8192 内搜索（范围更大时误报概率过高）
+fn recover_ihdr_size(ihdr: &PngChunk) -> Option<(u32, u32)> {
+    const MAX_SINGLE: u32 = 0xffff;
+    const MAX_BOTH: u32 = 8192;
+    let orig_w = u32::from_be_bytes(ihdr.data[0..4].try_into().ok()?);
+    let orig_h = u32::from_be_bytes(ihdr.data[4..8].try_into().ok()?);
+    let crc = |w: u32, h: u32| {
+        let mut data = ihdr.data.clone();
+        data[0..4].copy_from_slice(&w.to_be_bytes());
+        data[4..8].copy_from_slice(&h.to_be_bytes());
+        PngChunk { ty: *b"IHDR", data, crc: 0 }.computed_crc()
+    };
+    let base = crc(0, 0);
+    let by_height: HashMap<u32, u32> = (1..=MAX_SINGLE).map(|h| (crc(0, h), h)).collect();
+    let find_height = |w: u32, max: u32| {
+        by_height.get(&(ihdr.crc ^ crc(w, 0) ^ base)).copied().filter(|&h| h <= max)
+    };
+
+    if let Some(h) = find_height(orig_w, MAX_SINGLE) {
+        return Some((orig_w, h));
+    }
+    if let Some(w) = (1..=MAX_SINGLE).find(|&w| crc(w, orig_h) == ihdr.crc) {
+        return Some((w, orig_h));
+    }
+    (1..=MAX_BOTH).find_map(|w| find_height(w, MAX_BOTH).map(|h| (w, h)))
+}
+
+// 块异常：CRC 错误、未知/私有类型、顺序违反规范、关键块重复、辅助块过大
+pub fn chunk_anomalies(chunks: &[PngChunk]) -> Vec<(usize, String)> {
+    const KNOWN: &[&[u8; 4]] = &[
+        b"IHDR", b"PLTE", b"IDAT", b"IEND", b"tRNS", b"cHRM", b"gAMA", b"iCCP", b"sBIT", b"sRGB",
+        b"cICP", b"mDCV", b"cLLI", b"tEXt", b"zTXt", b"iTXt", b"bKGD", b"hIST", b"pHYs", b"sPLT",
+        b"eXIf", b"tIME", b"acTL", b"fcTL", b"fdAT", b"oFFs", b"pCAL", b"sCAL", b"gIFg", b"gIFx",
+        b"gIFt", b"sTER", b"dSIG", b"fRAc",
+        // 未注册但常见的私有块：ImageMagick（vpAg、caNv、orNT）、Apple（iDOT、CgBI）、
+        // Android 九宫格（npTc、npLb、npOl）、Fireworks（mkBF、mkBS、mkBT、mkTS、prVW）、
+        // Microsoft Office（msOG）、Doom 系引擎的偏移（grAb、alPh）
+        b"vpAg", b"caNv", b"orNT", b"iDOT", b"CgBI", b"npTc", b"npLb", b"npOl", b"mkBF", b"mkBS",
+        b"mkBT", b"mkTS", b"prVW", b"msOG", b"grAb", b"alPh",
+    ];
+    // 必须位于 PLTE 与 IDAT 之前 / 必须位于 IDAT 之前 / 必须位于 PLTE 之后
+    const BEFORE_PLTE: &[&[u8; 4]] = &[b"cHRM", b"gAMA", b"iCCP", b"sBIT", b"sRGB", b"cICP"];
+    const BEFORE_IDAT: &[&[u8; 4]] = &[b"PLTE", b"tRNS", b"bKGD", b"hIST", b"pHYs", b"sPLT", b"eXIf", b"oFFs", b"pCAL", b"sCAL", b"acTL"];
+    const AFTER_PLTE: &[&[u8; 4]] = &[b"tRNS", b"bKGD", b"hIST"];
+    // 定长辅助块的最大长度
+    const FIXED: &[(&[u8; 4], usize)] = &[
+        (b"gAMA", 4), (b"cHRM", 32), (b"sRGB", 1), (b"pHYs", 9), (b"tIME", 7), (b"bKGD", 6),
+        (b"sBIT", 4), (b"tRNS", 256), (b"oFFs", 9), (b"cICP", 4), (b"acTL", 8), (b"fcTL", 26),
+    ];
+    const MAX_ANCILLARY: usize = 256 * 1024;
+
+    let mut out = Vec::new();
+    let first = |t: &[u8; 4]| chunks.iter().position(|c| &c.ty == t);
+    let plte = first(b"PLTE");
+    let idat = first(b"IDAT");
+    let mut seen: Vec<[u8; 4]> = Vec::new();
+    for (idx, ch) in chunks.iter().enumerate() {
+        let ty = &ch.ty;
+        if !ch.crc_ok() {
+            out.push((idx, format!("CRC mismatch: stored 0x{:08x}, computed 0x{:08x}", ch.crc, ch.computed_crc())));
+        }
+        if !ty.iter().all(|b| b.is_ascii_alphabetic()) {
+            out.push((idx, "invalid chunk type".to_string()));
+        } else if KNOWN.contains(&ty) {
+            // APNG 的 acTL/fcTL/fdAT 等虽然形式上是私有块，但属于已知类型
+        } else if ch.is_private() {
+            out.push((idx, "private chunk type".to_string()));
+        } else {
+            out.push((idx, "unknown chunk type".to_string()));
+        }
+
+        if (idx == 0) != (ty == b"IHDR") {
+            out.push((idx, if idx == 0 { "first chunk is not IHDR" } else { "IHDR is not the first chunk" }.to_string()));
+        }
+        if ty == b"IEND" && idx + 1 != chunks.len() {
+            out.push((idx, "IEND is not the last chunk".to_string()));
+        }
+        if ty == b"IDAT" && idx > 0 && &chunks[idx - 1].ty != b"IDAT" && idat.is_some_and(|i| i < idx) {
+            out.push((idx, "IDAT chunks are not consecutive".to_string()));
+        }
+        if BEFORE_PLTE.contains(&ty) && (plte.is_some_and(|p| p < idx) || idat.is_some_and(|i| i < idx)) {
+            out.push((idx, "must precede PLTE and IDAT".to_string()));
+        } else if BEFORE_IDAT.contains(&ty) && idat.is_some_and(|i| i < idx) {
+            out.push((idx, "must precede IDAT".to_string()));
+        }
+        if AFTER_PLTE.contains(&ty) && plte.is_some_and(|p| p > idx) {
+            out.push((idx, "must follow PLTE".to_string()));
+        }
+
+        if ch.is_critical() && ty != b"IDAT" && seen.contains(ty) {
+            out.push((idx, "duplicate critical chunk".to_string()));
+        }
+        seen.push(*ty);
+
+        if !ch.is_critical() {
+            let limit = FIXED.iter().find(|(t, _)| *t == ty).map(|&(_, n)| n);
+            match limit {
+                Some(n) if ch.data.len() > n => {
+                    out.push((idx, format!("oversized: {} bytes, expected at most {}", ch.data.len(), n)));
+                }
+                None if ch.data.len() > MAX_ANCILLARY => {
+                    out.push((idx, format!("oversized ancillary chunk: {} bytes", ch.data.len())));
+                }
+                _ => {}
+            }
+        }
+    }
+    out
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
 }
 
-fn parse_png_text(data: &[u8]) -> Option<(String,String)> {
+fn parse_png_text(data: &[u8]) -> Option<MetaEntry> {
     // tEXt: keyword\0text
     let pos = data.iter().position(|&b| b == 0)?;
-    let (k, v) = data.split_at(pos);
-    let v = &v[1..];
-    Some((String::from_utf8_lossy(k).to_string(), String::from_utf8_lossy(v).to_string()))
+    Some(MetaEntry::new(String::from_utf8_lossy(&data[..pos]), &data[pos + 1..]))
 }
 
-fn parse_png_itxt(data: &[u8]) -> Option<(String,String)> {
-    // iTXt: keyword\0compressFlag\0langTag\0translated\0text (简化：忽略压缩与语言)
-    let mut parts = data.split(|&b| b == 0);
-    let k = parts.next()?;
-    let _compress = parts.next()?; // flag+method（简化合并）
-    let _lang = parts.next()?;
-    let _translated = parts.next()?;
-    let rest = parts.next().unwrap_or_default();
-    Some((String::from_utf8_lossy(k).to_string(), String::from_utf8_lossy(rest).to_string()))
+fn parse_png_ztxt(data: &[u8]) -> Option<MetaEntry> {
+    // zTXt: keyword\0compressionMethod compressedText
+    let pos = data.iter().position(|&b| b == 0)?;
+    let compressed = data.get(pos + 2..)?;
+    Some(MetaEntry::new(String::from_utf8_lossy(&data[..pos]), inflate_or_raw(compressed)))
 }
 
-fn parse_png_ztxt(data: &[u8]) -> Option<(String,String)> {
-    // zTXt: keyword\0compressionMethod\0compressedText
+fn parse_png_itxt(data: &[u8]) -> Option<MetaEntry> {
+    // iTXt: keyword\0 compressionFlag compressionMethod languageTag\0 translatedKeyword\0 text
     let pos = data.iter().position(|&b| b == 0)?;
-    let k = &data[..pos];
-    if pos + 2 > data.len() { return None; }
-    let _compression_method = data[pos + 1]; // 应该是 0 (deflate)
-    let compressed_data = &data[pos + 2..];
-    
-    // 解压数据
+    let key = String::from_utf8_lossy(&data[..pos]).to_string();
+    let compressed = *data.get(pos + 1)? != 0;
+    let mut parts = data.get(pos + 3..)?.splitn(3, |&b| b == 0);
+    let lang = String::from_utf8_lossy(parts.next()?).to_string();
+    let translated = parts.next()?.to_vec();
+    let text = parts.next().unwrap_or_default();
+    Some(MetaEntry {
+        value: if compressed { inflate_or_raw(text) } else { text.to_vec() },
+        lang: Some(lang).filter(|l| !l.is_empty()),
+        translated: Some(translated).filter(|t| !t.is_empty()),
+        ..MetaEntry::new(key, Vec::new())
+    })
+}
+
+fn parse_png_iccp(data: &[u8]) -> Vec<MetaEntry> {
+    // iCCP: profileName\0 compressionMethod compressedProfile
+    let Some(pos) = data.iter().position(|&b| b == 0) else { return Vec::new(); };
+    let mut out = vec![MetaEntry::new("iCCP name", &data[..pos])];
+    let Some(profile) = data.get(pos + 2..).map(inflate_or_raw) else { return out; };
+    out.extend(parse_icc_profile(&profile, "iCCP"));
+    out
+}
+
+// ICC 配置文件中的文本类标签及超出声明大小的追加数据，关键字以 prefix 开头
+fn parse_icc_profile(profile: &[u8], prefix: &str) -> Vec<MetaEntry> {
+    let mut out = Vec::new();
+    let rd32 = |p: usize| profile.get(p..p + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize);
+
+    // 标签表中的文本类标签：text / desc（ASCII）/ mluc（UTF-16BE）
+    let count = rd32(128).unwrap_or(0).min(256);
+    for i in 0..count {
+        let e = 132 + i * 12;
+        let (Some(off), Some(size)) = (rd32(e + 4), rd32(e + 8)) else { break; };
+        let Some(tag) = profile.get(off..off.saturating_add(size)) else { continue; };
+        let sig = String::from_utf8_lossy(&profile[e..e + 4]).to_string();
+        let text: Vec<u8> = match tag.get(..4) {
+            Some(b"text") => tag.get(8..).unwrap_or_default().to_vec(),
+            Some(b"desc") => {
+                let n = tag.get(8..12).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize).unwrap_or(0);
+                tag.get(12..12usize.saturating_add(n)).unwrap_or_default().to_vec()
+            }
+            Some(b"mluc") => {
+                let rec = tag.get(16..28).unwrap_or_default();
+                let len = rec.get(4..8).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize).unwrap_or(0);
+                let off = rec.get(8..12).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize).unwrap_or(0);
+                let units: Vec<u16> = tag.get(off..off.saturating_add(len)).unwrap_or_default()
+                    .chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+                String::from_utf16_lossy(&units).into_bytes()
+            }
+            _ => continue,
+        };
+        let text = text.iter().rposition(|&b| b != 0).map(|p| text[..=p].to_vec()).unwrap_or_default();
+        if !text.is_empty() { out.push(MetaEntry::new(format!("{} {}", prefix, sig.trim_end()), text)); }
+    }
+
+    // 超出头部声明大小的追加数据
+    if let Some(declared) = rd32(0)
+        && profile.len() > declared {
+        out.push(MetaEntry::new(format!("{} appended", prefix), &profile[declared..]));
+    }
+    out
+}
+
+fn parse_png_time(data: &[u8]) -> Option<MetaEntry> {
+    // tIME: year(2) month day hour minute second
+    let d = data.get(..7)?;
+    let time = format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        u16::from_be_bytes([d[0], d[1]]), d[2], d[3], d[4], d[5], d[6]
+    );
+    Some(MetaEntry::new("tIME", time))
+}
+
+fn parse_png_splt(data: &[u8]) -> Option<MetaEntry> {
+    // sPLT: paletteName\0 sampleDepth entries；名称作为关键字，条目原样保留
+    let pos = data.iter().position(|&b| b == 0)?;
+    let name = String::from_utf8_lossy(&data[..pos]);
+    Some(MetaEntry::new(format!("sPLT {}", name), data.get(pos + 2..)?))
+}
+
+// 解压失败时保留原始字节，避免丢失数据
+fn inflate_or_raw(data: &[u8]) -> Vec<u8> {
     use flate2::read::ZlibDecoder;
-    use std::io::Read;
-    let mut decoder = ZlibDecoder::new(compressed_data);
-    let mut decompressed = Vec::new();
-    if decoder.read_to_end(&mut decompressed).is_err() {
-        return None;
+    let mut out = Vec::new();
+    match ZlibDecoder::new(data).read_to_end(&mut out) {
+        Ok(_) => out,
+        Err(_) => data.to_vec(),
     }
-    
-    Some((String::from_utf8_lossy(k).to_string(), String::from_utf8_lossy(&decompressed).to_string()))
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 4x4 画布，第二帧只覆盖右下角 2x2
+    fn apng() -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut enc = png::Encoder::new(&mut buf, 4, 4);
+        enc.set_color(png::ColorType::Rgba);
+        enc.set_animated(2, 0).unwrap();
+        let mut w = enc.write_header().unwrap();
+        w.write_image_data(&[0x40; 64]).unwrap();
+        w.set_frame_dimension(2, 2).unwrap();
+        w.set_frame_position(2, 2).unwrap();
+        w.write_image_data(&[0x41; 16]).unwrap();
+        w.finish().unwrap();
+        buf
+    }
+
+    // 8x8 灰度隔行 PNG，每个 pass 的像素值为 pass 编号，filter 均为 0
+    fn interlaced() -> Vec<u8> {
+        use std::io::Write;
+        let mut raw = Vec::new();
+        for (n, &(x0, y0, dx, dy)) in ADAM7.iter().enumerate() {
+            for _ in (y0..8).step_by(dy) {
+                raw.push(0);
+                raw.extend((x0..8).step_by(dx).map(|_| n as u8 + 1));
+            }
+        }
+        let mut z = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        z.write_all(&raw).unwrap();
+        let chunk = |ty: &[u8; 4], data: Vec<u8>| {
+            let mut c = PngChunk { ty: *ty, data, crc: 0 };
+            c.crc = c.computed_crc();
+            c
+        };
+        assemble_png(&[
+            chunk(b"IHDR", vec![0, 0, 0, 8, 0, 0, 0, 8, 8, 0, 0, 0, 1]),
+            chunk(b"IDAT", z.finish().unwrap()),
+            chunk(b"IEND", Vec::new()),
+        ])
+    }
+
+    #[test]
+    fn interlaced_imagedata_rows() {
+        let img = Image::load_png_bytes(&interlaced()).unwrap();
+        let rows = img.ihdr().unwrap().rows();
+        let lens: Vec<usize> = rows.iter().map(|&(_, len)| len).collect();
+        assert_eq!(lens, [2, 2, 3, 3, 3, 5, 5, 5, 5, 5, 5, 9, 9, 9, 9]);
+        assert_eq!(img.imagedata.len(), img.ihdr().unwrap().imagedata_len());
+
+        // 按列遍历 imagedata：第一列应全部是 filter 字节，第二列为各行首个像素
+        let mut opts = crate::opts::Options::default();
+        opts.order = crate::opts::OrderSpec::Explicit(vec!["yb".into()]);
+        opts.bits = Some(vec![8]);
+        opts.bit_order = Some(crate::opts::BitOrder::Lsb);
+        opts.byte_source = Some(crate::opts::ByteSource::ImageData);
+        opts.limit = 0;
+        let data = crate::extractor::extract(&img, &opts, &mut String::new());
+        assert_eq!(&data[..rows.len()], &[0; 15]);
+        assert_eq!(&data[rows.len()..rows.len() * 2], &[1, 2, 3, 4, 4, 5, 5, 6, 6, 6, 6, 7, 7, 7, 7]);
+
+        // 各 pass 作为子图像，不再额外按 adam7 顺序扫描
+        let labels: Vec<&str> = img.subimages.iter().map(|(l, _)| l.as_str()).collect();
+        assert_eq!(labels, ["pass:1", "pass:2", "pass:3", "pass:4", "pass:5", "pass:6", "pass:7"]);
+        assert!(!img.adam7_pass && img.subimages.iter().all(|(_, sub)| sub.adam7_pass));
+    }
+
+    #[test]
+    fn interlaced_filter_bytes() {
+        let mut img = Image::load_png_bytes(&interlaced()).unwrap();
+        assert_eq!(img.filter_bytes(), Some(vec![0; 15]));
+        // 各 pass 的行首：行长 2,2,3,3,3,5×6,9×4
+        let starts = [0, 2, 4, 7, 10, 13, 18, 23, 28, 33, 38, 43, 52, 61, 70];
+        for (i, &pos) in starts.iter().enumerate() {
+            img.imagedata[pos] = i as u8 % 5;
+        }
+        assert_eq!(img.filter_bytes(), Some(vec![0, 1, 2, 3, 4, 0, 1, 2, 3, 4, 0, 1, 2, 3, 4]));
+        // 截断的 imagedata 没有完整的行表
+        img.imagedata.truncate(60);
+        assert_eq!(img.filter_bytes(), None);
+    }
+
+    #[test]
+    fn common_private_chunks_are_not_anomalies() {
+        let chunk = |ty: &[u8; 4], data: &[u8]| {
+            let mut c = PngChunk { ty: *ty, data: data.to_vec(), crc: 0 };
+            c.crc = c.computed_crc();
+            c
+        };
+        let ihdr = chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0]);
+        let (idat, iend) = (chunk(b"IDAT", &[]), chunk(b"IEND", &[]));
+        let chunks = [ihdr.clone(), chunk(b"vpAg", &[0; 9]), idat.clone(), iend.clone()];
+        assert!(chunk_anomalies(&chunks).is_empty());
+        let chunks = [ihdr, chunk(b"hiDe", b"secret"), idat, iend];
+        assert_eq!(chunk_anomalies(&chunks), [(1, "private chunk type".to_string())]);
+    }
+
+    #[test]
+    fn misnamed_file_is_sniffed() {
+        let path = std::env::temp_dir().join(format!("zsteg-misnamed-{}.jpg", std::process::id()));
+        std::fs::write(&path, apng()).unwrap();
+        let img = Image::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(img.unwrap().format, ImgFormat::Png);
+    }
+
+    // samples/flower.png（270x180）的 IHDR 高度改为 100，CRC 保持原值
+    fn lowered_ihdr() -> Vec<u8> {
+        let mut buf = std::fs::read(format!("{}/samples/flower.png", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        buf[20..24].copy_from_slice(&100u32.to_be_bytes());
+        buf
+    }
+
+    #[test]
+    fn recover_lowered_ihdr_height() {
+        let img = Image::load_png_bytes(&lowered_ihdr()).unwrap();
+        assert_eq!((img.width, img.height), (270, 100));
+        assert_eq!(img.recovered_size, Some((270, 180)));
+
+        let fixed = img.with_size(270, 180).unwrap();
+        assert_eq!((fixed.width, fixed.height), (270, 180));
+        assert!(fixed.chunks[0].crc_ok());
+        let orig = std::fs::read(format!("{}/samples/flower.png", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        assert_eq!(fixed.pixels, Image::load_png_bytes(&orig).unwrap().pixels);
+    }
+
+    #[test]
+    fn apng_frame_ihdr_has_valid_crc() {
+        let img = Image::load_png_bytes(&apng()).unwrap();
+        let (_, frame) = img.subimages.iter().find(|(label, _)| label == "frame:1").unwrap();
+        assert_eq!(&frame.chunks[0].data[0..8], &[0, 0, 0, 2, 0, 0, 0, 2]);
+        assert!(frame.chunks[0].crc_ok());
+        assert!(chunk_anomalies(&frame.chunks).is_empty());
+    }
+
+    #[test]
+    fn truncated_icc_tags() {
+        // 头部 128 字节 + 标签表，标签数据只有签名或长度字段越界
+        let tags: [&[u8]; 3] = [
+            b"text",
+            b"desc\0\0\0\0\xff\xff\xff\xff",
+            b"mluc\0\0\0\0\0\0\0\x01\0\0\0\x0cenUS\xff\xff\xff\xf0\xff\xff\xff\xf0",
+        ];
+        let mut p = vec![0u8; 128];
+        p.extend(3u32.to_be_bytes());
+        let mut off = 128 + 4 + tags.len() * 12;
+        for (sig, t) in [b"cprt", b"desc", b"dmnd"].iter().zip(tags) {
+            p.extend(*sig);
+            p.extend((off as u32).to_be_bytes());
+            p.extend((t.len() as u32).to_be_bytes());
+            off += t.len();
+        }
+        for t in tags { p.extend(t); }
+        let n = p.len() as u32;
+        p[..4].copy_from_slice(&n.to_be_bytes());
+        assert!(parse_icc_profile(&p, "iCCP").is_empty());
+    }
+
+    #[test]
+    fn pam_comments_and_trailer() {
+        let mut buf = b"P7\n# made by hand\nWIDTH 2\nHEIGHT 1\n#flag{pam}\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB\nENDHDR\n".to_vec();
+        buf.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+        buf.extend_from_slice(b"trailing");
+        let img = Image::load_pnm_bytes(&buf).unwrap();
+        assert_eq!((img.width, img.height), (2, 1));
+        let meta: Vec<(&str, &[u8])> = img.metadata.iter().map(|m| (m.key.as_str(), &m.value[..])).collect();
+        assert_eq!(meta, [("PNM comment", &b" made by hand"[..]), ("PNM comment", b"flag{pam}"), ("PAM TUPLTYPE", b"RGB")]);
+        assert_eq!(img.imagedata, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(img.pixels, [1, 2, 3, 0xff, 4, 5, 6, 0xff]);
+        assert_eq!(img.extradata, [b"trailing".to_vec()]);
+    }
+
+    #[test]
+    fn jpeg_com_iptc_and_trailer() {
+        let segment = |marker: u8, body: &[u8]| {
+            let mut out = vec![0xFF, marker];
+            out.extend_from_slice(&(body.len() as u16 + 2).to_be_bytes());
+            out.extend_from_slice(body);
+            out
+        };
+        // APP13：8BIM 0x0404（空名称补齐到 2 字节）内含 IPTC 2:120 Caption
+        let iptc = b"\x1C\x02\x78\x00\x0Bflag{iptc}!";
+        let mut app13 = b"Photoshop 3.0\x008BIM\x04\x04\0\0".to_vec();
+        app13.extend_from_slice(&(iptc.len() as u32).to_be_bytes());
+        app13.extend_from_slice(iptc);
+        let mut buf = vec![0xFF, 0xD8];
+        buf.extend(segment(0xFE, b"flag{com}"));
+        buf.extend(segment(0xED, &app13));
+        buf.extend(segment(0xC0, &[8, 0, 8, 0, 8, 1, 1, 0x11, 0]));
+        buf.extend_from_slice(&[0xFF, 0xD9]);
+        buf.extend_from_slice(b"after EOI");
+
+        let img = Image::load_jpeg_bytes(&buf).unwrap();
+        assert_eq!((img.width, img.height), (8, 8));
+        let meta: Vec<(&str, &[u8])> = img.metadata.iter().map(|m| (m.key.as_str(), &m.value[..])).collect();
+        assert_eq!(meta, [("JPEG COM", &b"flag{com}"[..]), ("JPEG IPTC Caption", b"flag{iptc}!")]);
+        assert_eq!(img.extradata, [b"after EOI".to_vec()]);
+        assert!(img.jpeg.unwrap().has_eoi);
+    }
+}
@@ -0,0 +1,458 @@
+// JPEG 容器解析：按顺序列出标记段（SOS 段附带其后的熵编码数据）、段之间的多余字节以及 EOI 之后的数据
+use anyhow::{bail, Result};
+
+#[derive(Debug, Clone)]
+pub struct Segment {
+	pub marker: u8,
+	pub offset: usize, // 0xFF 所在位置
+	pub data: Vec<u8>, // 段内容（不含长度字段）
+	pub scan: Vec<u8>, // 仅 SOS：其后的熵编码数据（含 0xFF00 填充与 RST 标记）
+	pub garbage: Vec<u8>, // 该标记之前不属于任何段的字节
+}
+
+#[derive(Debug, Clone)]
+pub struct Jpeg {
+	pub segments: Vec<Segment>,
+	pub width: u32,
+	pub height: u32,
+	pub has_eoi: bool,
+	pub trailer: Vec<u8>, // EOI 之后的数据
+	pub coefficients: Vec<i16>, // 量化后的 DCT 系数，见 decode_coefficients
+	pub luma: Option<Plane>, // 第一个分量按块位置排列的系数，供统计分析
+}
+
+// 单个分量的系数平面：bw×bh 个块，块内及量化表均为 zigzag 顺序
+#[derive(Debug, Clone)]
+pub struct Plane {
+	pub bw: usize,
+	pub bh: usize,
+	pub blocks: Vec<[i16; 64]>,
+	pub quant: [u16; 64],
+}
+
+pub fn marker_name(m: u8) -> String {
+	match m {
+		0xD8 => "SOI".into(),
+		0xD9 => "EOI".into(),
+		0xDA => "SOS".into(),
+		0xDB => "DQT".into(),
+		0xC4 => "DHT".into(),
+		0xCC => "DAC".into(),
+		0xDD => "DRI".into(),
+		0xDC => "DNL".into(),
+		0xDE => "DHP".into(),
+		0xDF => "EXP".into(),
+		0xFE => "COM".into(),
+		0xC0..=0xCF => format!("SOF{}", m - 0xC0),
+		0xD0..=0xD7 => format!("RST{}", m - 0xD0),
+		0xE0..=0xEF => format!("APP{}", m - 0xE0),
+		_ => format!("0xFF{:02X}", m),
+	}
+}
+
+// 规范中定义的标记（JPG 扩展 0xF0–0xFD 及保留值视为未知）
+pub fn is_known(m: u8) -> bool {
+	matches!(m, 0xC0..=0xEF | 0xFE)
+}
+
+// 无长度字段的独立标记
+fn standalone(m: u8) -> bool {
+	matches!(m, 0xD0..=0xD9 | 0x01)
+}
+
+pub fn parse(data: &[u8]) -> Result<Jpeg> {
+	if !data.starts_with(&[0xFF, 0xD8]) {
+		bail!("Not a JPEG file");
+	}
+	let mut jpeg = Jpeg { segments: Vec::new(), width: 0, height: 0, has_eoi: false, trailer: Vec::new(), coefficients: Vec::new(), luma: None };
+	let mut pos = 0;
+	while pos < data.len() {
+		// 找下一个标记：跳过填充的 0xFF，其余字节记为多余数据
+		let start = pos;
+		while pos < data.len() && !(data[pos] == 0xFF && data.get(pos + 1).is_some_and(|&b| b != 0xFF && b != 0)) { pos += 1; }
+		if pos >= data.len() { break; }
+		let garbage = if data[start..pos].iter().all(|&b| b == 0xFF) { Vec::new() } else { data[start..pos].to_vec() };
+		let offset = pos;
+		let marker = data[pos + 1];
+		pos += 2;
+
+		let mut seg = Segment { marker, offset, data: Vec::new(), scan: Vec::new(), garbage };
+		if !standalone(marker) {
+			let Some(len) = data.get(pos..pos + 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as usize) else { break; };
+			let end = (pos + len.max(2)).min(data.len());
+			seg.data = data.get(pos + 2..end).unwrap_or_default().to_vec();
+			pos = end;
+		}
+		if matches!(marker, 0xC0..=0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF) && seg.data.len() >= 5 && jpeg.width == 0 {
+			jpeg.height = u16::from_be_bytes([seg.data[1], seg.data[2]]) as u32;
+			jpeg.width = u16::from_be_bytes([seg.data[3], seg.data[4]]) as u32;
+		}
+		// 熵编码数据直到下一个非 RST 标记
+		if marker == 0xDA {
+			let s = pos;
+			while pos + 1 < data.len() && !(data[pos] == 0xFF && data[pos + 1] != 0 && !(0xD0..=0xD7).contains(&data[pos + 1])) { pos += 1; }
+			if pos + 1 >= data.len() { pos = data.len(); }
+			seg.scan = data[s..pos].to_vec();
+		}
+		jpeg.segments.push(seg);
+		if marker == 0xD9 {
+			jpeg.has_eoi = true;
+			jpeg.trailer = data[pos..].to_vec();
+			break;
+		}
+	}
+	(jpeg.coefficients, jpeg.luma) = jpeg.decode_coefficients();
+	Ok(jpeg)
+}
+
+impl Jpeg {
+	// 结构异常：(段索引, 描述)
+	pub fn anomalies(&self) -> Vec<(usize, String)> {
+		let mut out = Vec::new();
+		for (i, seg) in self.segments.iter().enumerate() {
+			if !is_known(seg.marker) {
+				out.push((i, format!("unknown marker {} at offset {}", marker_name(seg.marker), seg.offset)));
+			}
+			if !seg.garbage.is_empty() {
+				out.push((i, format!("{} bytes of data before {} at offset {}", seg.garbage.len(), marker_name(seg.marker), seg.offset)));
+			}
+		}
+		if let Some(last) = self.segments.last().filter(|_| !self.has_eoi) {
+			out.push((self.segments.len() - 1, format!("no EOI after {}", marker_name(last.marker))));
+		}
+		out
+	}
+}
+
+// 规范 Huffman 表（F.2.2.3）：按码长的最大码值与符号起始位置
+struct Huffman {
+	maxcode: [i32; 18],
+	valptr: [i32; 17],
+	mincode: [i32; 17],
+	values: Vec<u8>,
+}
+
+impl Huffman {
+	fn new(counts: &[u8], values: &[u8]) -> Self {
+		let mut h = Huffman { maxcode: [-1; 18], valptr: [0; 17], mincode: [0; 17], values: values.to_vec() };
+		let (mut code, mut k) = (0i32, 0i32);
+		for len in 1..=16 {
+			let n = counts[len - 1] as i32;
+			if n > 0 {
+				h.valptr[len] = k;
+				h.mincode[len] = code;
+				code += n;
+				k += n;
+				h.maxcode[len] = code - 1;
+			}
+			code <<= 1;
+		}
+		h.maxcode[17] = i32::MAX;
+		h
+	}
+
+	fn decode(&self, r: &mut BitReader) -> Option<u8> {
+		let mut code = 0i32;
+		for len in 1..=16 {
+			code = (code << 1) | r.bit()? as i32;
+			if code <= self.maxcode[len] {
+				return self.values.get((self.valptr[len] + code - self.mincode[len]) as usize).copied();
+			}
+		}
+		None
+	}
+}
+
+// 熵编码数据的位读取：去除 0xFF00 填充，遇到标记即停止
+struct BitReader<'a> {
+	data: &'a [u8],
+	pos: usize,
+	cur: u8,
+	left: u8,
+}
+
+impl BitReader<'_> {
+	fn bit(&mut self) -> Option<u8> {
+		if self.left == 0 {
+			let b = *self.data.get(self.pos)?;
+			if b == 0xFF {
+				if self.data.get(self.pos + 1) != Some(&0) { return None; }
+				self.pos += 1;
+			}
+			self.pos += 1;
+			self.cur = b;
+			self.left = 8;
+		}
+		self.left -= 1;
+		Some((self.cur >> self.left) & 1)
+	}
+
+	fn receive(&mut self, n: u8) -> Option<i32> {
+		let mut v = 0i32;
+		for _ in 0..n { v = (v << 1) | self.bit()? as i32; }
+		// 符号扩展（F.2.2.1）
+		Some(if n > 0 && v < 1 << (n - 1) { v - (1 << n) + 1 } else { v })
+	}
+
+	// 重启间隔：丢弃剩余位并跳过 RST 标记
+	fn restart(&mut self) {
+		self.left = 0;
+		while self.pos + 1 < self.data.len() && !(self.data[self.pos] == 0xFF && (0xD0..=0xD7).contains(&self.data[self.pos + 1])) {
+			self.pos += 1;
+		}
+		self.pos += 2;
+	}
+}
+
+impl Jpeg {
+	// 量化后的 DCT 系数：按扫描中出现的顺序逐块排列，块内为 zigzag 顺序（含 DC）。
+	// 仅支持 Huffman 编码的顺序式 JPEG（SOF0/SOF1），其他情况返回空
+	fn decode_coefficients(&self) -> (Vec<i16>, Option<Plane>) {
+		let mut out = Vec::new();
+		let mut luma = None;
+		let mut dc_tables: [Option<Huffman>; 4] = Default::default();
+		let mut ac_tables: [Option<Huffman>; 4] = Default::default();
+		let mut quant = [[1u16; 64]; 4];
+		let mut luma_tq = 0;
+		let mut comps: Vec<(u8, usize, usize)> = Vec::new(); // (id, h, v)
+		let mut restart = 0;
+		for seg in &self.segments {
+			let d = &seg.data;
+			match seg.marker {
+				0xC0 | 0xC1 if d.len() >= 6 => {
+					comps = d[6..].chunks_exact(3).take(d[5] as usize).map(|c| (c[0], (c[1] >> 4).max(1) as usize, (c[1] & 15).max(1) as usize)).collect();
+					// 分量数为 0 或分量表被截断：不是有效的帧头
+					let Some(&(_, h, v)) = comps.first() else { return (Vec::new(), None); };
+					luma_tq = d[8] as usize & 3;
+					let hmax = comps.iter().map(|c| c.1).max().unwrap_or(1);
+					let vmax = comps.iter().map(|c| c.2).max().unwrap_or(1);
+					// 只保留覆盖图像的块，交织扫描中 MCU 补齐的块丢弃
+					let bw = (self.width as usize * h).div_ceil(hmax).div_ceil(8);
+					let bh = (self.height as usize * v).div_ceil(vmax).div_ceil(8);
+					// 每块至少 2 位（DC 码 + EOB），声明的尺寸远超扫描数据时不分配平面
+					let scan_bytes: usize = self.segments.iter().map(|s| s.scan.len()).sum();
+					if bw * bh <= scan_bytes * 4 {
+						luma = Some(Plane { bw, bh, blocks: vec![[0; 64]; bw * bh], quant: [1; 64] });
+					}
+				}
+				0xC2 | 0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return (Vec::new(), None),
+				// DQT：每张表 1 字节精度/编号，随后 64 个 8 位或 16 位值
+				0xDB => {
+					let mut p = 0;
+					while let Some(&pq) = d.get(p) {
+						let size = if pq >> 4 == 0 { 1 } else { 2 };
+						let Some(vals) = d.get(p + 1..p + 1 + 64 * size) else { break; };
+						for (k, q) in quant[pq as usize & 3].iter_mut().enumerate() {
+							*q = if size == 1 { vals[k] as u16 } else { u16::from_be_bytes([vals[2 * k], vals[2 * k + 1]]) };
+						}
+						p += 1 + 64 * size;
+					}
+				}
+				0xC4 => {
+					let mut p = 0;
+					while p + 17 <= d.len() {
+						let (class, id) = (d[p] >> 4, (d[p] & 3) as usize);
+						let counts = &d[p + 1..p + 17];
+						let n: usize = counts.iter().map(|&c| c as usize).sum();
+						let Some(values) = d.get(p + 17..p + 17 + n) else { break; };
+						let table = Some(Huffman::new(counts, values));
+						if class == 0 { dc_tables[id] = table; } else { ac_tables[id] = table; }
+						p += 17 + n;
+					}
+				}
+				0xDD if d.len() >= 2 => restart = u16::from_be_bytes([d[0], d[1]]) as usize,
+				// 扫描解码失败时保留已解出的系数
+				0xDA if !comps.is_empty() && !self.decode_scan(seg, &comps, &dc_tables, &ac_tables, restart, &mut out, luma.as_mut()) => break,
+				_ => {}
+			}
+		}
+		// DQT 可能出现在 SOF 之后
+		if let Some(plane) = luma.as_mut() { plane.quant = quant[luma_tq]; }
+		(out, luma)
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	fn decode_scan(&self, seg: &Segment, comps: &[(u8, usize, usize)], dc: &[Option<Huffman>; 4], ac: &[Option<Huffman>; 4], restart: usize, out: &mut Vec<i16>, mut luma: Option<&mut Plane>) -> bool {
+		let d = &seg.data;
+		let Some(&n) = d.first() else { return false; };
+		// 扫描中的分量：(在 SOF 中的索引, DC 表, AC 表)
+		let mut scan = Vec::new();
+		for c in d.get(1..1 + n as usize * 2).unwrap_or_default().chunks_exact(2) {
+			let Some(i) = comps.iter().position(|&(id, _, _)| id == c[0]) else { return false; };
+			let (Some(dct), Some(act)) = (dc[(c[1] >> 4) as usize & 3].as_ref(), ac[(c[1] & 3) as usize].as_ref()) else { return false; };
+			scan.push((i, dct, act));
+		}
+		if scan.is_empty() { return false; }
+
+		let hmax = comps.iter().map(|c| c.1).max().unwrap_or(1);
+		let vmax = comps.iter().map(|c| c.2).max().unwrap_or(1);
+		let (w, h) = (self.width as usize, self.height as usize);
+		// 单分量扫描不交织，每个块即一个 MCU；否则每个 MCU 含各分量 h×v 个块
+		let (mcus, blocks_per): (usize, Vec<usize>) = if scan.len() == 1 {
+			let (_, ch, cv) = comps[scan[0].0];
+			let bw = (w * ch).div_ceil(hmax).div_ceil(8);
+			let bh = (h * cv).div_ceil(vmax).div_ceil(8);
+			(bw * bh, vec![1])
+		} else {
+			let mcus = w.div_ceil(8 * hmax) * h.div_ceil(8 * vmax);
+			(mcus, scan.iter().map(|&(i, _, _)| comps[i].1 * comps[i].2).collect())
+		};
+
+		// 非交织扫描按分量自身的块宽逐行排列
+		let (ch0, cv0) = (comps[0].1, comps[0].2);
+		let mcux = w.div_ceil(8 * hmax);
+		let single_bw = (w * ch0).div_ceil(hmax).div_ceil(8);
+
+		let mut r = BitReader { data: &seg.scan, pos: 0, cur: 0, left: 0 };
+		let mut pred = vec![0i32; scan.len()];
+		for m in 0..mcus {
+			if restart > 0 && m > 0 && m % restart == 0 {
+				r.restart();
+				pred.iter_mut().for_each(|p| *p = 0);
+			}
+			for (si, &(ci, dct, act)) in scan.iter().enumerate() {
+				for b in 0..blocks_per[si] {
+					let mut block = [0i16; 64];
+					let Some(t) = dct.decode(&mut r) else { return false; };
+					// DC 差值的位数最多 16（基线为 11），DHT 中的其他符号是无效表
+					if t > 16 { return false; }
+					let Some(diff) = r.receive(t) else { return false; };
+					pred[si] += diff;
+					block[0] = pred[si] as i16;
+					let mut k = 1;
+					while k < 64 {
+						let Some(rs) = act.decode(&mut r) else { return false; };
+						let (run, size) = ((rs >> 4) as usize, rs & 15);
+						if size == 0 {
+							if run != 15 { break; }
+							k += 16;
+							continue;
+						}
+						k += run;
+						let Some(v) = r.receive(size) else { return false; };
+						if k < 64 { block[k] = v as i16; }
+						k += 1;
+					}
+					out.extend_from_slice(&block);
+					if ci == 0 && let Some(plane) = luma.as_deref_mut() {
+						let (x, y) = if scan.len() == 1 {
+							(m % single_bw, m / single_bw)
+						} else {
+							((m % mcux) * ch0 + b % ch0, (m / mcux) * cv0 + b / ch0)
+						};
+						if x < plane.bw && let Some(dst) = plane.blocks.get_mut(y * plane.bw + x) {
+							*dst = block;
+						}
+					}
+				}
+			}
+		}
+		true
+	}
+}
+
+// JSteg 使用的系数：跳过 0 与 1，其余取低 8 位（补码，LSB 与原值一致）
+pub fn jsteg_bytes(coefficients: &[i16]) -> Vec<u8> {
+	coefficients.iter().filter(|&&c| c != 0 && c != 1).map(|&c| c as u8).collect()
+}
+
+// JSteg 头部：5 位长度字段宽度 n，随后 n 位负载长度（字节），均高位在前；
+// lsb 为按 b1,lsb 打包的系数 LSB。返回长度与负载
+pub fn jsteg_payload(lsb: &[u8]) -> Option<(usize, Vec<u8>)> {
+	let bit = |i: usize| lsb.get(i / 8).map(|b| (b >> (7 - i % 8)) & 1);
+	let read = |start: usize, n: usize| (start..start + n).try_fold(0usize, |acc, i| Some(acc << 1 | bit(i)? as usize));
+	let n = read(0, 5)?;
+	let size = read(5, n)?;
+	// 编码器使用最短宽度，最高位必为 1
+	if n == 0 || size >> (n - 1) != 1 || 5 + n + size * 8 > lsb.len() * 8 {
+		return None;
+	}
+	let payload = (0..size).map(|k| read(5 + n + k * 8, 8).map(|v| v as u8)).collect::<Option<Vec<u8>>>()?;
+	Some((size, payload))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn segment(marker: u8, body: &[u8]) -> Vec<u8> {
+		let mut out = vec![0xFF, marker];
+		out.extend_from_slice(&(body.len() as u16 + 2).to_be_bytes());
+		out.extend_from_slice(body);
+		out
+	}
+
+	fn jpeg(sof: &[u8], scan: &[u8]) -> Vec<u8> {
+		let mut data = vec![0xFF, 0xD8];
+		data.extend(segment(0xC0, sof));
+		data.extend(segment(0xDA, &[1, 1, 0, 0, 63, 0]));
+		data.extend_from_slice(scan);
+		data.extend_from_slice(&[0xFF, 0xD9]);
+		data
+	}
+
+	#[test]
+	fn sof_without_components() {
+		// Nf = 0，但段长度足够读到第一个分量的位置
+		let j = parse(&jpeg(&[8, 0, 16, 0, 16, 0, 1, 0x11, 0], &[0; 16])).unwrap();
+		assert_eq!((j.width, j.height), (16, 16));
+		assert!(j.coefficients.is_empty() && j.luma.is_none());
+	}
+
+	#[test]
+	fn truncated_sof() {
+		let j = parse(&jpeg(&[8, 0, 16, 0, 16, 3, 1], &[])).unwrap();
+		assert!(j.coefficients.is_empty() && j.luma.is_none());
+	}
+
+	#[test]
+	fn huge_size_without_scan_data() {
+		let j = parse(&jpeg(&[8, 0xFF, 0xFF, 0xFF, 0xFF, 1, 1, 0x11, 0], &[0; 4])).unwrap();
+		assert!(j.luma.is_none());
+	}
+
+	#[test]
+	fn scan_without_huffman_tables() {
+		let j = parse(&jpeg(&[8, 0, 8, 0, 8, 1, 1, 0x11, 0], &[0x12, 0x34, 0x56])).unwrap();
+		assert!(j.coefficients.is_empty());
+		assert!(j.has_eoi);
+	}
+
+	#[test]
+	fn dc_symbol_out_of_range() {
+		// DC 表只有一个 1 位码，符号为 40；AC 表只有 EOB
+		let mut dht = vec![0x00, 1];
+		dht.extend_from_slice(&[0; 15]);
+		dht.push(40);
+		dht.extend_from_slice(&[0x10, 1]);
+		dht.extend_from_slice(&[0; 15]);
+		dht.push(0);
+		let mut data = vec![0xFF, 0xD8];
+		data.extend(segment(0xC4, &dht));
+		data.extend(&jpeg(&[8, 0, 8, 0, 8, 1, 1, 0x11, 0], &[0; 16])[2..]);
+		let j = parse(&data).unwrap();
+		assert!(j.coefficients.is_empty());
+	}
+
+	#[test]
+	fn jsteg_header_from_coefficients() {
+		let payload = b"JSteg";
+		// 5 位宽度 3，3 位长度 5（101），随后负载
+		let mut bits = vec![0, 0, 0, 1, 1, 1, 0, 1];
+		bits.extend(payload.iter().flat_map(|&b| (0..8).rev().map(move |i| (b >> i) & 1)));
+		// 每个比特写入一个非 0/1 系数的 LSB，中间夹杂 JSteg 跳过的 0 与 1
+		let mut coefficients = Vec::new();
+		for (i, &b) in bits.iter().enumerate() {
+			let base = [6i16, -3, 2, -18, 41][i % 5];
+			coefficients.push((base & !1) | b as i16);
+			coefficients.extend_from_slice(&[0, 1][..i % 3]);
+		}
+		let lsb: Vec<u8> = jsteg_bytes(&coefficients).chunks(8).map(|c| c.iter().enumerate().fold(0, |acc, (k, &v)| acc | (v & 1) << (7 - k))).collect();
+		assert_eq!(jsteg_payload(&lsb), Some((5, payload.to_vec())));
+
+		// 长度字段不是最短宽度（宽度 4，长度 0100）：不是 JSteg 头
+		let mut padded = lsb.clone();
+		padded[0] = 0b0010_0010;
+		assert_eq!(jsteg_payload(&padded), None);
+	}
+}
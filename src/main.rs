@@ -1,5 +1,6 @@
 use clap::{ArgAction, Parser, ValueEnum};
 use colored::*;
+use anyhow::Context;
 use std::path::PathBuf;
 
 mod opts;
@@ -10,12 +11,26 @@ mod extractor;
 mod result;
 mod hexdump;
 mod file_cmd;
+mod prng;
+mod crack;
+mod exif;
+mod gif;
+mod pnm;
+mod tiff;
+mod webp;
+mod ico;
+mod tga;
+mod qoi;
+mod jpeg;
+mod steganalysis;
+mod audio;
+mod archive;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, ValueEnum)]
 enum BitOrder { Lsb, Msb }
 
 #[derive(Parser, Debug)]
-#[command(name = "zsteg", version, about = "detect stegano-hidden data in PNG & BMP")] 
+#[command(name = "zsteg", version, about = "detect stegano-hidden data in PNG, BMP, JPEG and other images, in WAV/AU audio, and inside ZIP/7z/tar archives")] 
 struct Cli {
     /// try all known methods
     #[arg(short = 'a', long = "all", action = ArgAction::SetTrue)]
@@ -25,11 +40,15 @@ struct Cli {
     #[arg(short = 'E', long = "extract")]
     extract: Option<String>,
 
-    /// pixel iteration order: ALL,xy,yx,XY,YX,xY,Xy,bY,... (default: auto)
+    /// pixel iteration order: ALL,xy,yx,XY,YX,xY,Xy,bY,adam7,rand:<java|python|c|xorshift>:<seed>,... (default: auto)
     #[arg(short = 'o', long = "order")]
     order: Option<String>,
 
-    /// channels (R/G/B/A) or any combination, comma separated
+    /// byte source for bY-style orders: imagedata (filtered), rawpixels (unfiltered), dct (JPEG coefficients) (default: both on PNG)
+    #[arg(long = "source", value_name = "SRC")]
+    source: Option<String>,
+
+    /// channels (R/G/B/A, audio l/r/c0..) or any combination, comma separated
     #[arg(short = 'c', long = "channels")]
     channels: Option<String>,
 
@@ -85,6 +104,14 @@ struct Cli {
     #[arg(short = 'n', long = "min-str-len")]
     min_str_len: Option<usize>,
 
+    /// try every password from FILE against encrypted payloads (OpenStego, wbStego 4.x)
+    #[arg(long = "wordlist", value_name = "FILE")]
+    wordlist: Option<PathBuf>,
+
+    /// re-decode PNG with the IHDR width/height recovered from its CRC
+    #[arg(long = "fix-ihdr", action = ArgAction::SetTrue)]
+    fix_ihdr: bool,
+
     /// Run verbosely (can be used multiple times)
     #[arg(short = 'v', long = "verbose", action = ArgAction::Count)]
     verbose: u8,
@@ -97,22 +124,49 @@ struct Cli {
     #[arg(short = 'C', long = "color")]
     color: Option<bool>,
 
-    /// filename.png [param_string]
-    input: PathBuf,
+    /// scan every supported file under DIR recursively (can be used multiple times)
+    #[arg(short = 'r', long = "recursive", value_name = "DIR")]
+    recursive: Vec<PathBuf>,
+
+    /// with -r, only scan files whose name matches PATTERN, like '*.png' (can be used multiple times)
+    #[arg(long = "glob", value_name = "PATTERN")]
+    glob: Vec<String>,
 
-    /// optional param_string shortcut like "2b,b,lsb,xy"
-    param_string: Option<String>,
+    /// filename.png ... [param_string], or "-" to read from stdin; param_string is a shortcut like "2b,b,lsb,xy"
+    inputs: Vec<String>,
 }
 
 fn main() -> anyhow::Result<()> {
+    // 批量模式的退出码：2 = 有输入无法加载（优先），1 = 有发现，0 = 什么都没找到；
+    // 单个文件时保持原有约定：出错为 1，否则为 0
+    let code = run()?;
+    if code != 0 {
+        std::process::exit(code);
+    }
+    Ok(())
+}
+
+fn run() -> anyhow::Result<i32> {
     let cli = Cli::parse();
 
     if let Some(force) = cli.color { colored::control::set_override(force); }
 
+    // 最后一个位置参数既不是文件、又能解析为参数串时，视为 param_string（与 Ruby 版 "zsteg FILE PARAMS" 一致）
+    let mut inputs = cli.inputs.clone();
+    let param_string = match inputs.last() {
+        Some(last) if last != "-" && !std::path::Path::new(last).exists() && decode_param_string(last).is_ok() => inputs.pop(),
+        _ => None,
+    };
+    let mut files: Vec<PathBuf> = inputs.into_iter().map(PathBuf::from).collect();
+    files.extend(walk_inputs(&cli.recursive, &cli.glob)?);
+    if files.is_empty() {
+        anyhow::bail!("no input files (give FILE, \"-\" or -r DIR)");
+    }
+
     // 解析选项：先默认，再合并 CLI，再合并 param_string（与 Ruby 行为一致：行内覆盖）
     let mut options = Options::default();
     merge_cli_into_options(&mut options, &cli)?;
-    if let Some(ps) = &cli.param_string {
+    if let Some(ps) = &param_string {
         let o2 = decode_param_string(ps)?;
         // 简单字段按优先级覆盖
         if o2.bit_order.is_some() { options.bit_order = o2.bit_order; }
@@ -122,48 +176,27 @@ fn main() -> anyhow::Result<()> {
         if !matches!(o2.pixel_align, opts::PixelAlignSpec::None) { options.pixel_align = o2.pixel_align; }
         if !matches!(o2.prime, opts::PrimeSpec::None) { options.prime = o2.prime; }
         if o2.zlib_flag { options.zlib_flag = true; }
+        if o2.byte_source.is_some() { options.byte_source = o2.byte_source; }
         options.extra_checks &= o2.extra_checks; // 任一侧关闭则关闭
     }
 
-    let img = imageio::Image::load(&cli.input)?;
+    if let Some(path) = &cli.wordlist {
+        options.passwords = std::rc::Rc::new(load_wordlist(path)?);
+    }
 
     if let Some(expr) = &cli.extract {
+        if files.len() != 1 {
+            anyhow::bail!("-E takes exactly one input file");
+        }
         // 提取模式：处理特殊名称或解析参数字符串
         use std::io::Write;
-        let data = if expr == "imagedata" {
-            img.imagedata.clone()
-        } else if expr.starts_with("chunk:") {
-            // 格式: chunk:N 或 chunk:N:TYPE
-            let parts: Vec<&str> = expr.split(':').collect();
-            if parts.len() >= 2 {
-                let idx: usize = parts[1].parse()?;
-                img.chunks.get(idx).map(|c| c.data.clone()).unwrap_or_default()
-            } else {
-                Vec::new()
-            }
-        } else {
-            // 普通参数字符串，如 "b1,r,lsb,xy"
-            let extract_opts = decode_param_string(expr)?;
-            let mut final_opts = options.clone();
-            if extract_opts.bit_order.is_some() { final_opts.bit_order = extract_opts.bit_order; }
-            if extract_opts.bits.is_some() { final_opts.bits = extract_opts.bits; }
-            if !matches!(extract_opts.order, opts::OrderSpec::Auto) { final_opts.order = extract_opts.order; }
-            if extract_opts.channels.is_some() { final_opts.channels = extract_opts.channels; }
-            if !matches!(extract_opts.prime, opts::PrimeSpec::None) { final_opts.prime = extract_opts.prime; }
-            
-            // 提取模式：如果 limit 是默认值，则使用无限制（与原版行为一致）
-            if final_opts.limit == 256 { // DEFAULT_LIMIT
-                final_opts.limit = 0; // 0 means no limit in extractor
-            }
-            
-            let mut title = String::new();
-            extractor::extract(&img, &final_opts, &mut title)
-        };
-        
+        let img = load_input(&files[0], &cli)?;
+        let data = extract_payload(&img, expr, &options)?;
+
         if options.zlib_flag {
             let mut dec = flate2::read::ZlibDecoder::new(&data[..]);
             let mut out = Vec::new();
-            if let Ok(_) = std::io::Read::read_to_end(&mut dec, &mut out) { 
+            if std::io::Read::read_to_end(&mut dec, &mut out).is_ok() { 
                 print!("{}", String::from_utf8_lossy(&out)); 
             } else { 
                 eprintln!("cannot decompress with zlib"); 
@@ -171,16 +204,246 @@ fn main() -> anyhow::Result<()> {
         } else {
             std::io::stdout().write_all(&data)?;
         }
-        return Ok(());
+        return Ok(0);
     }
     
-    // 正常检查模式
-    if options.verbose >= 0 {
-        println!("{} {}", "[.]".green(), cli.input.display());
+    // 正常检查模式；多个文件或 -r 时单个文件出错不中断，最后输出汇总
+    let batch = files.len() > 1 || !cli.recursive.is_empty();
+    let mut summary = Vec::new();
+    for (i, path) in files.iter().enumerate() {
+        if batch && i > 0 && options.verbose >= 0 { println!(); }
+        if options.verbose >= 0 {
+            println!("{} {}", "[.]".green(), path.display());
+        }
+        let img = match load_input(path, &cli) {
+            Ok(img) => img,
+            Err(e) if batch => {
+                eprintln!("{} {}: {:#}", "[!]".red(), path.display(), e);
+                summary.push((path, Err(e)));
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let findings = checker::Checker::new(&img, &options).check();
+        summary.push((path, Ok(findings)));
+    }
+
+    if !batch {
+        return Ok(0);
     }
+    print_summary(&summary);
+    Ok(batch_exit_code(&summary))
+}
 
-    let c = checker::Checker::new(&img, &options);
-    let _ = c.check();
+// "-"：从 stdin 读取，按魔数识别格式
+fn load_input(path: &std::path::Path, cli: &Cli) -> anyhow::Result<imageio::Image> {
+    let mut img = if path.as_os_str() == "-" {
+        let mut buf = Vec::new();
+        std::io::Read::read_to_end(&mut std::io::stdin(), &mut buf)?;
+        imageio::Image::from_bytes(&buf)?
+    } else {
+        imageio::Image::load(path)?
+    };
+    // IHDR 宽高被篡改：按 CRC 爆破出的尺寸重新解码，使隐藏的行参与扫描
+    if cli.fix_ihdr && let Some((w, h)) = img.recovered_size {
+        img = img.with_size(w, h)?;
+        img.recovered_size = Some((w, h));
+    }
+    Ok(img)
+}
 
-    Ok(())
+// --wordlist：每行一个口令，忽略空行与行尾的 \r；批量模式下所有文件共用
+fn load_wordlist(path: &std::path::Path) -> anyhow::Result<Vec<String>> {
+    let buf = std::fs::read(path).with_context(|| format!("read wordlist {}", path.display()))?;
+    Ok(buf
+        .split(|&b| b == b'\n')
+        .map(|l| String::from_utf8_lossy(l.strip_suffix(b"\r").unwrap_or(l)).to_string())
+        .filter(|l| !l.is_empty())
+        .collect())
+}
+
+// -r：递归列出目录下的文件（按文件名排序）；给了 --glob 时按文件名或相对路径匹配，否则只取支持的扩展名
+fn walk_inputs(dirs: &[PathBuf], globs: &[String]) -> anyhow::Result<Vec<PathBuf>> {
+    let patterns = globs.iter()
+        .map(|g| glob::Pattern::new(g).with_context(|| format!("bad glob {:?}", g)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let mut files = Vec::new();
+    for dir in dirs {
+        for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+            let entry = entry.with_context(|| format!("walk {}", dir.display()))?;
+            if !entry.file_type().is_file() { continue; }
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy();
+            let keep = if patterns.is_empty() {
+                let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+                imageio::Image::is_supported_ext(&ext)
+            } else {
+                let rel = path.strip_prefix(dir).unwrap_or(path);
+                patterns.iter().any(|p| p.matches(&name) || p.matches_path(rel))
+            };
+            if keep { files.push(path.to_path_buf()); }
+        }
+    }
+    Ok(files)
+}
+
+// 退出码 1 所需的最低评分：排除不太像文本的片段（0.2）和不常见的文件类型、无扩展名的 wbStego 头（0.3）
+const FOUND_SCORE: f64 = 0.35;
+// 汇总表中结果列的最大可见宽度
+const SUMMARY_WIDTH: usize = 60;
+
+// 批量模式汇总：每个文件评分最高的结果
+fn batch_exit_code(summary: &[(&PathBuf, anyhow::Result<Vec<result::Finding>>)]) -> i32 {
+    let found = summary.iter().any(|(_, r)| r.as_ref().is_ok_and(|f| f.iter().any(|f| f.result.score() >= FOUND_SCORE)));
+    if summary.iter().any(|(_, r)| r.is_err()) { 2 } else if found { 1 } else { 0 }
+}
+
+fn print_summary(summary: &[(&PathBuf, anyhow::Result<Vec<result::Finding>>)]) {
+    let width = summary.iter().map(|(p, _)| p.display().to_string().len()).max().unwrap_or(0).max(4);
+    println!();
+    println!("{} summary", "[=]".green());
+    println!("{:<width$}  {:>5}  top finding", "file", "score");
+    for (path, r) in summary {
+        let path = path.display().to_string();
+        match r {
+            // 评分相同时取最先出现的
+            Ok(findings) => match findings.iter().rev().filter(|f| f.result.score() > 0.0).max_by(|a, b| a.result.score().total_cmp(&b.result.score())) {
+                Some(f) => println!("{:<width$}  {:>5.2}  {}", path, f.result.score(), truncate_visible(&format!("{}.. {}", f.title, f.result), SUMMARY_WIDTH)),
+                None => println!("{:<width$}  {:>5}  {}", path, "-", "nothing".bright_black()),
+            },
+            Err(e) => println!("{:<width$}  {:>5}  {}", path, "-", format!("error: {:#}", e).red()),
+        }
+    }
+}
+
+// 按可见字符截断，ANSI 颜色序列不计宽度；截断时补上重置序列
+fn truncate_visible(s: &str, max: usize) -> String {
+    let mut out = String::new();
+    let (mut visible, mut colored) = (0, false);
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            out.push(c);
+            for c in chars.by_ref() {
+                out.push(c);
+                if c.is_ascii_alphabetic() { break; }
+            }
+            colored = true;
+            continue;
+        }
+        if visible == max {
+            out.push('…');
+            if colored { out.push_str("\x1b[0m"); }
+            break;
+        }
+        out.push(c);
+        visible += 1;
+    }
+    out
+}
+
+// -E 提取：特殊名称、子图像前缀（如 "frame:1:b1,rgb,lsb,xy"）或参数字符串
+fn extract_payload(img: &imageio::Image, expr: &str, options: &Options) -> anyhow::Result<Vec<u8>> {
+    if let Some((sub, rest)) = img.subimages.iter().find_map(|(label, sub)| {
+        expr.strip_prefix(label.as_str())?.strip_prefix(':').map(|rest| (sub, rest))
+    }) {
+        return extract_payload(sub, rest, options);
+    }
+
+    let data = if expr == "imagedata" {
+        img.imagedata.clone()
+    } else if expr == "idat:trailer" {
+        img.idat_trailer()
+    } else if expr == "imagedata:excess" {
+        img.imagedata_excess().to_vec()
+    } else if expr == "rawpixels" {
+        img.rawpixels().unwrap_or_default()
+    } else if expr == "stegpng" {
+        // steganography-png 工具的完整载荷
+        match checker::Checker::check_steganography_png(img) {
+            Some(result::DetectResult::SteganographyPng { data, .. }) => data,
+            _ => Vec::new(),
+        }
+    } else if expr.starts_with("scanline") {
+        // scanline / scanline:lsb / scanline:bin
+        checker::Checker::scanline_streams(img)
+            .into_iter()
+            .find(|(name, _)| *name == expr)
+            .map(|(_, data)| data)
+            .unwrap_or_default()
+    } else if let Some(idx) = expr.strip_prefix("segment:") {
+        // 格式: segment:N 或 segment:N:NAME（JPEG 标记段内容）
+        let idx: usize = idx.split(':').next().unwrap_or_default().parse()?;
+        img.jpeg.as_ref().and_then(|j| j.segments.get(idx)).map(|s| s.data.clone()).unwrap_or_default()
+    } else if expr.starts_with("chunk:") {
+        // 格式: chunk:N 或 chunk:N:TYPE
+        let parts: Vec<&str> = expr.split(':').collect();
+        if parts.len() >= 2 {
+            let idx: usize = parts[1].parse()?;
+            img.chunks.get(idx).map(|c| c.data.clone()).unwrap_or_default()
+        } else {
+            Vec::new()
+        }
+    } else {
+        // 普通参数字符串，如 "b1,r,lsb,xy"
+        let extract_opts = decode_param_string(expr)?;
+        let mut final_opts = options.clone();
+        if extract_opts.bit_order.is_some() { final_opts.bit_order = extract_opts.bit_order; }
+        if extract_opts.bits.is_some() { final_opts.bits = extract_opts.bits; }
+        if !matches!(extract_opts.order, opts::OrderSpec::Auto) { final_opts.order = extract_opts.order; }
+        if extract_opts.channels.is_some() { final_opts.channels = extract_opts.channels; }
+        if !matches!(extract_opts.prime, opts::PrimeSpec::None) { final_opts.prime = extract_opts.prime; }
+        if extract_opts.byte_source.is_some() { final_opts.byte_source = extract_opts.byte_source; }
+        
+        // 提取模式：如果 limit 是默认值，则使用无限制（与原版行为一致）
+        if final_opts.limit == 256 { // DEFAULT_LIMIT
+            final_opts.limit = 0; // 0 means no limit in extractor
+        }
+        
+        let mut title = String::new();
+        extractor::extract(img, &final_opts, &mut title)
+    };
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use result::{DetectResult, Finding};
+
+    #[test]
+    fn batch_exit_codes() {
+        let path = PathBuf::from("a.png");
+        let text = || Ok(vec![Finding { title: "b1,r,lsb,xy".into(), result: DetectResult::WholeText("hello world".into()) }]);
+        assert_eq!(batch_exit_code(&[(&path, Ok(Vec::new())), (&path, Ok(Vec::new()))]), 0);
+        assert_eq!(batch_exit_code(&[(&path, Ok(Vec::new())), (&path, text())]), 1);
+        // 加载失败优先于有发现
+        assert_eq!(batch_exit_code(&[(&path, text()), (&path, Err(anyhow::anyhow!("no such file")))]), 2);
+        // 噪声级别的结果不算发现
+        let noise = || Ok(vec![Finding { title: "b1,g,msb,xy".into(), result: DetectResult::PartialText { text: "x;Q".into(), offset: 7 } }]);
+        assert_eq!(batch_exit_code(&[(&path, noise())]), 0);
+    }
+
+    #[test]
+    fn fix_ihdr_reloads_with_recovered_size() {
+        // samples/flower.png（270x180）的 IHDR 高度改为 100，CRC 保持原值
+        let mut buf = std::fs::read(format!("{}/samples/flower.png", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        buf[20..24].copy_from_slice(&100u32.to_be_bytes());
+        let path = std::env::temp_dir().join(format!("zsteg-fix-ihdr-{}.png", std::process::id()));
+        std::fs::write(&path, buf).unwrap();
+        let arg = path.to_str().unwrap();
+        let img = load_input(&path, &Cli::parse_from(["zsteg", arg])).unwrap();
+        assert_eq!(img.height, 100);
+        let img = load_input(&path, &Cli::parse_from(["zsteg", "--fix-ihdr", arg])).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((img.width, img.height, img.recovered_size), (270, 180, Some((270, 180))));
+    }
+
+    #[test]
+    fn summary_truncation() {
+        assert_eq!(truncate_visible("short", 10), "short");
+        assert_eq!(truncate_visible("0123456789abc", 10), "0123456789…");
+        // 颜色序列不计宽度，截断后补重置
+        assert_eq!(truncate_visible("\x1b[90mtext: \x1b[0m\x1b[91m\"hello world\"\x1b[0m", 10), "\x1b[90mtext: \x1b[0m\x1b[91m\"hel…\x1b[0m");
+    }
 }
@@ -33,6 +33,35 @@ pub struct Options {
     pub min_str_len: usize,
     pub extra_checks: bool,
     pub zlib_flag: bool,
+    pub passwords: std::rc::Rc<Vec<String>>, // --wordlist 中的口令，main 中读取一次
+    pub byte_source: Option<ByteSource>, // None: PNG 上 imagedata 与 rawpixels 都扫描
+}
+
+// 字节迭代（bY 等顺序）的数据来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteSource {
+    ImageData, // 解压后的扫描线，PNG 每行带 filter 字节
+    RawPixels, // 去除 filter 后的原生样本字节，不含 filter 字节
+    Dct, // JPEG 量化 DCT 系数（扫描顺序，跳过 0/1，同 JSteg）
+}
+
+impl ByteSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ByteSource::ImageData => "imagedata",
+            ByteSource::RawPixels => "rawpixels",
+            ByteSource::Dct => "dct",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "imagedata" => Ok(ByteSource::ImageData),
+            "rawpixels" => Ok(ByteSource::RawPixels),
+            "dct" => Ok(ByteSource::Dct),
+            _ => bail!("unknown byte source {:?} (imagedata, rawpixels, dct)", s),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -46,6 +75,63 @@ pub enum OrderSpec {
     Auto,
     All,
     Explicit(Vec<String>),
+    Random { prng: Prng, seed: u64 }, // rand:<prng>:<seed>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prng {
+    Java,     // java.util.Random + Collections.shuffle
+    Python,   // MT19937 + random.shuffle
+    Glibc,    // srand/rand + Fisher-Yates
+    Xorshift, // xorshift32 + Fisher-Yates
+}
+
+impl Prng {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Prng::Java => "java",
+            Prng::Python => "python",
+            Prng::Glibc => "c",
+            Prng::Xorshift => "xorshift",
+        }
+    }
+}
+
+impl std::fmt::Display for OrderSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderSpec::Auto => write!(f, "auto"),
+            OrderSpec::All => write!(f, "ALL"),
+            OrderSpec::Explicit(v) => write!(f, "{}", v.join(",")),
+            OrderSpec::Random { prng, seed } => write!(f, "rand:{}:{}", prng.name(), seed),
+        }
+    }
+}
+
+/// 解析单个顺序，如 "xy"、"bY" 或 "rand:java:1234"
+pub fn parse_order(s: &str) -> Result<OrderSpec> {
+    let Some(rest) = s.strip_prefix("rand:") else {
+        return Ok(OrderSpec::Explicit(vec![s.to_string()]));
+    };
+    let Some((name, seed)) = rest.split_once(':') else {
+        bail!("invalid random order {:?}, expected rand:<prng>:<seed>", s);
+    };
+    let prng = match name.to_lowercase().as_str() {
+        "java" => Prng::Java,
+        "python" | "py" | "mt" | "mt19937" => Prng::Python,
+        "c" | "glibc" | "rand" => Prng::Glibc,
+        "xorshift" | "xorshift32" => Prng::Xorshift,
+        _ => bail!("unknown prng {:?} (java, python, c, xorshift)", name),
+    };
+    let seed = if let Some(hex) = seed.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16)?
+    } else if let Some(neg) = seed.strip_prefix('-') {
+        // Python 的 random.seed(-n) 等价于 seed(n)；Java/C 按补码截断
+        if prng == Prng::Python { neg.parse::<u64>()? } else { (neg.parse::<i64>()?).wrapping_neg() as u64 }
+    } else {
+        seed.parse::<u64>()?
+    };
+    Ok(OrderSpec::Random { prng, seed })
 }
 
 impl Default for Options {
@@ -68,6 +154,8 @@ impl Default for Options {
             min_str_len: 8,
             extra_checks: true,
             zlib_flag: false,
+            passwords: Default::default(),
+            byte_source: None,
         }
     }
 }
@@ -123,6 +211,23 @@ fn parse_bits_single(x: &str) -> Result<u16> {
     bail!("invalid bits value: {}", x)
 }
 
+// 音频声道组合：l、r 或 c0、c1…，如 "lr"、"c0c2"
+fn is_audio_channels(x: &str) -> bool {
+    let mut rest = x;
+    while let Some(c) = rest.chars().next() {
+        rest = match c {
+            'l' | 'r' => &rest[1..],
+            'c' => {
+                let n = rest[1..].chars().take_while(|c| c.is_ascii_digit()).count();
+                if n == 0 { return false; }
+                &rest[1 + n..]
+            }
+            _ => return false,
+        };
+    }
+    true
+}
+
 pub fn decode_param_string(s: &str) -> Result<Options> {
     let mut o = Options::default();
     let mut pixel_align_flag = false;
@@ -133,6 +238,7 @@ pub fn decode_param_string(s: &str) -> Result<Options> {
             "msb" => o.bit_order = Some(BitOrder::Msb),
             "prime" => { o.prime = PrimeSpec::Only; o.extra_checks = false; },
             "zlib" => { o.zlib_flag = true; },
+            "imagedata" | "rawpixels" | "dct" => o.byte_source = Some(ByteSource::parse(x)?),
             _ => {
                 // 尝试解析 bits: b1, b2, 1b, 2b 等格式
                 // 但要排除 rgb, bgr 等通道名称
@@ -140,9 +246,8 @@ pub fn decode_param_string(s: &str) -> Result<Options> {
                     if let Some(cap) = x.strip_prefix('b') {
                         // b1, b2, b3 等格式
                         if cap.chars().all(|c| c.is_ascii_digit() || c == 'p') && !cap.is_empty() {
-                            if cap.ends_with('p') {
+                            if let Some(num_part) = cap.strip_suffix('p') {
                                 pixel_align_flag = true;
-                                let num_part = &cap[..cap.len()-1];
                                 o.bits = Some(parse_bits(num_part, &mut pixel_align_flag)?);
                             } else {
                                 o.bits = Some(parse_bits(cap, &mut pixel_align_flag)?);
@@ -161,12 +266,16 @@ pub fn decode_param_string(s: &str) -> Result<Options> {
                     }
                 }
                 
+                if x.starts_with("rand:") {
+                    o.order = parse_order(x)?;
+                    continue;
+                }
                 if x.eq_ignore_ascii_case("xy") || x.eq_ignore_ascii_case("yx") ||
-                   x.eq_ignore_ascii_case("yb") || x.eq_ignore_ascii_case("by") {
+                   x.eq_ignore_ascii_case("yb") || x.eq_ignore_ascii_case("by") || x == "adam7" {
                     o.order = OrderSpec::Explicit(vec![x.to_string()]);
                     continue;
                 }
-                if x.chars().all(|c| matches!(c, 'r'|'g'|'b'|'a')) && !x.is_empty() {
+                if (x.chars().all(|c| matches!(c, 'r'|'g'|'b'|'a')) || is_audio_channels(x)) && !x.is_empty() {
                     o.channels = Some(vec![x.to_string()]);
                     o.extra_checks = false;
                     continue;
@@ -188,7 +297,11 @@ pub fn merge_cli_into_options(base: &mut Options, cli: &crate::Cli) -> Result<()
         base.extra_checks = true; // 显式启用额外检查
     }
     if let Some(s) = &cli.extract { let _ = s; /* 占位：extract 模式下静音等 */ }
-    if let Some(o) = &cli.order { base.order = OrderSpec::Explicit(o.split(',').map(|x| x.to_string()).collect()); }
+    if let Some(o) = &cli.order {
+        // rand:<prng>:<seed> 在这里校验并规范化，便于 checker 按字符串逐个处理
+        let v: Vec<String> = o.split(',').map(|x| parse_order(x).map(|o| o.to_string())).collect::<Result<_>>()?;
+        base.order = if v.len() == 1 { parse_order(&v[0])? } else { OrderSpec::Explicit(v) };
+    }
     if let Some(c) = &cli.channels { base.channels = Some(c.split(',').map(|x| x.to_string()).collect()); base.extra_checks = false; }
     if let Some(b) = &cli.bits { let mut p = false; base.bits = Some(parse_bits(b, &mut p)?); if p { base.pixel_align = PixelAlignSpec::Only; } base.extra_checks = false; }
     if cli.lsb { base.bit_order = Some(BitOrder::Lsb); }
@@ -203,6 +316,7 @@ pub fn merge_cli_into_options(base: &mut Options, cli: &crate::Cli) -> Result<()
     if cli.no_strings { base.strings = Some(StringsMode::None); }
     if let Some(s) = &cli.strings { base.strings = Some(match s.to_lowercase().as_str() { "first" => StringsMode::First, "all" => StringsMode::All, "longest" => StringsMode::Longest, "none"|"no" => StringsMode::None, _ => StringsMode::First }); }
     if let Some(n) = cli.min_str_len { base.min_str_len = n; }
+    if let Some(src) = &cli.source { base.byte_source = Some(ByteSource::parse(src)?); }
     base.verbose = (cli.verbose as i32) - (cli.quiet as i32);
     Ok(())
 }
@@ -0,0 +1,178 @@
+// Netpbm（P1–P6）与 PAM（P7）解析：头部注释、原始栅格数据与栅格之后的多余字节
+use anyhow::{bail, Context, Result};
+
+#[derive(Debug, Clone)]
+pub struct Pnm {
+	pub width: usize,
+	pub height: usize,
+	pub depth: usize, // 每像素样本数
+	pub tupltype: String, // 仅 P7
+	pub comments: Vec<Vec<u8>>, // 头部 '#' 注释（不含 '#'）
+	pub raster: Vec<u8>, // 二进制格式为文件中的原始栅格；ASCII 格式为样本值（>255 时大端 16 位）
+	pub samples: Vec<u16>, // 按行排列的样本值，PBM 每像素一个 0/1
+	pub trailer: Vec<u8>, // 栅格之后的数据
+}
+
+pub fn parse(data: &[u8]) -> Result<Pnm> {
+	let kind = match data.get(..2) {
+		Some([b'P', k @ b'1'..=b'7']) => k - b'0',
+		_ => bail!("Not a PNM file"),
+	};
+	let mut pos = 2;
+	let mut comments = Vec::new();
+	let (width, height, depth, maxval, tupltype) = if kind == 7 {
+		parse_pam_header(data, &mut pos, &mut comments)?
+	} else {
+		let mut next = || -> Result<u32> {
+			let t = next_token(data, &mut pos, &mut comments).context("truncated PNM header")?;
+			std::str::from_utf8(t)?.parse().with_context(|| format!("bad PNM header value {:?}", String::from_utf8_lossy(t)))
+		};
+		let (w, h) = (next()? as usize, next()? as usize);
+		let maxval = if kind == 1 || kind == 4 { 1 } else { next()? };
+		let depth = if kind == 3 || kind == 6 { 3 } else { 1 };
+		// 二进制栅格前只有一个空白字符
+		if kind >= 4 { pos += 1; }
+		(w, h, depth, maxval, String::new())
+	};
+	if maxval == 0 || maxval > 65535 {
+		bail!("bad PNM maxval {}", maxval);
+	}
+
+	let count = width.checked_mul(height).and_then(|n| n.checked_mul(depth)).filter(|&n| n <= 1 << 28).context("PNM image too large")?;
+	let wide = maxval > 255;
+	let (raster, samples, end) = match kind {
+		// ASCII：P1 的 0/1 可以不加分隔
+		1..=3 => {
+			let mut samples = Vec::with_capacity(count);
+			while samples.len() < count {
+				let Some(t) = next_token(data, &mut pos, &mut comments) else { break; };
+				if kind == 1 {
+					samples.extend(t.iter().map(|&c| (c == b'1') as u16));
+				} else {
+					samples.push(std::str::from_utf8(t)?.parse::<u32>().unwrap_or(0).min(maxval) as u16);
+				}
+			}
+			samples.resize(count, 0);
+			let raster = if wide {
+				samples.iter().flat_map(|s| s.to_be_bytes()).collect()
+			} else {
+				samples.iter().map(|&s| s as u8).collect()
+			};
+			(raster, samples, pos)
+		}
+		// PBM 二进制：每行按字节补齐，1 位一个像素（MSB 在前）
+		4 => {
+			let line = width.div_ceil(8);
+			let end = (pos + line * height).min(data.len());
+			let raster = data[pos.min(end)..end].to_vec();
+			let samples = (0..height)
+				.flat_map(|y| (0..width).map(move |x| (y, x)))
+				.map(|(y, x)| raster.get(y * line + x / 8).map_or(0, |b| ((b >> (7 - x % 8)) & 1) as u16))
+				.collect();
+			(raster, samples, end)
+		}
+		_ => {
+			let len = count * if wide { 2 } else { 1 };
+			let end = (pos + len).min(data.len());
+			let raster = data[pos.min(end)..end].to_vec();
+			let mut samples: Vec<u16> = if wide {
+				raster.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect()
+			} else {
+				raster.iter().map(|&b| b as u16).collect()
+			};
+			samples.resize(count, 0);
+			(raster, samples, end)
+		}
+	};
+	// ASCII 格式栅格末尾的空白不算多余数据
+	let mut end = end;
+	if kind <= 3 {
+		while data.get(end).is_some_and(|c| c.is_ascii_whitespace()) { end += 1; }
+	}
+
+	Ok(Pnm {
+		width,
+		height,
+		depth,
+		tupltype,
+		comments,
+		raster,
+		samples,
+		trailer: data.get(end..).unwrap_or_default().to_vec(),
+	})
+}
+
+// P7 头部：按行的 "KEY value"，以 ENDHDR 结束
+fn parse_pam_header(data: &[u8], pos: &mut usize, comments: &mut Vec<Vec<u8>>) -> Result<(usize, usize, usize, u32, String)> {
+	let (mut w, mut h, mut depth, mut maxval) = (None, None, None, None);
+	let mut tupltype = Vec::new();
+	loop {
+		let rest = data.get(*pos..).unwrap_or_default();
+		if rest.is_empty() { bail!("truncated PAM header"); }
+		let eol = rest.iter().position(|&c| c == b'\n').unwrap_or(rest.len());
+		let line = &rest[..eol];
+		*pos += eol + 1;
+		if let Some(c) = line.strip_prefix(b"#") {
+			comments.push(c.to_vec());
+			continue;
+		}
+		let line = String::from_utf8_lossy(line);
+		let mut it = line.split_whitespace();
+		let (Some(key), value) = (it.next(), it.next()) else { continue; };
+		let num = || value.and_then(|v| v.parse::<u32>().ok()).with_context(|| format!("bad PAM {}", key));
+		match key {
+			"ENDHDR" => break,
+			"WIDTH" => w = Some(num()? as usize),
+			"HEIGHT" => h = Some(num()? as usize),
+			"DEPTH" => depth = Some(num()? as usize),
+			"MAXVAL" => maxval = Some(num()?),
+			"TUPLTYPE" => tupltype.extend(value.map(str::to_string)),
+			_ => {}
+		}
+	}
+	Ok((
+		w.context("PAM missing WIDTH")?,
+		h.context("PAM missing HEIGHT")?,
+		depth.context("PAM missing DEPTH")?,
+		maxval.context("PAM missing MAXVAL")?,
+		tupltype.join(" "),
+	))
+}
+
+// 下一个以空白分隔的记号，顺带收集 '#' 注释
+fn next_token<'a>(data: &'a [u8], pos: &mut usize, comments: &mut Vec<Vec<u8>>) -> Option<&'a [u8]> {
+	loop {
+		match data.get(*pos)? {
+			c if c.is_ascii_whitespace() => *pos += 1,
+			b'#' => {
+				let rest = &data[*pos + 1..];
+				let eol = rest.iter().position(|&c| c == b'\n' || c == b'\r').unwrap_or(rest.len());
+				comments.push(rest[..eol].to_vec());
+				*pos += eol + 1;
+			}
+			_ => break,
+		}
+	}
+	let start = *pos;
+	while data.get(*pos).is_some_and(|c| !c.is_ascii_whitespace() && *c != b'#') { *pos += 1; }
+	Some(&data[start..*pos])
+}
+
+impl Pnm {
+	// 转为 RGBA8：样本值不按 maxval 缩放以保留低位，16 位样本取低字节
+	pub fn to_rgba(&self) -> Vec<u8> {
+		let d = self.depth.max(1);
+		self.samples
+			.chunks(d)
+			.flat_map(|px| {
+				let s = |i: usize| px.get(i).map_or(0, |&v| v as u8);
+				match d {
+					1 => [s(0), s(0), s(0), 0xff],
+					2 => [s(0), s(0), s(0), s(1)],
+					3 => [s(0), s(1), s(2), 0xff],
+					_ => [s(0), s(1), s(2), s(3)],
+				}
+			})
+			.collect()
+	}
+}
@@ -0,0 +1,212 @@
+use crate::opts::Prng;
+
+// 按各语言标准库的洗牌方式，生成 0..n 的伪随机排列
+// 用于模拟 "rand:<prng>:<seed>" 像素遍历顺序
+pub fn permutation(prng: Prng, seed: u64, n: usize) -> Vec<usize> {
+	let mut v: Vec<usize> = (0..n).collect();
+	if n < 2 { return v; }
+	match prng {
+		Prng::Java => {
+			// Collections.shuffle(list, new Random(seed))
+			let mut r = JavaRandom::new(seed as i64);
+			for i in (2..=n).rev() {
+				let j = r.next_int(i as i32) as usize;
+				v.swap(i - 1, j);
+			}
+		}
+		Prng::Python => {
+			// random.seed(seed); random.shuffle(list)
+			let mut r = Mt19937::new_python(seed);
+			for i in (1..n).rev() {
+				let j = r.randbelow(i as u32 + 1) as usize;
+				v.swap(i, j);
+			}
+		}
+		Prng::Glibc => {
+			// srand(seed); for (i = n-1; i > 0; i--) j = rand() % (i+1);
+			let mut r = GlibcRand::new(seed as u32);
+			for i in (1..n).rev() {
+				let j = r.next() as usize % (i + 1);
+				v.swap(i, j);
+			}
+		}
+		Prng::Xorshift => {
+			// xorshift32，洗牌方式同 C 版本
+			let mut x = seed as u32;
+			if x == 0 { x = 1; }
+			for i in (1..n).rev() {
+				x ^= x << 13;
+				x ^= x >> 17;
+				x ^= x << 5;
+				let j = x as usize % (i + 1);
+				v.swap(i, j);
+			}
+		}
+	}
+	v
+}
+
+// java.util.Random（48 位线性同余）
+struct JavaRandom {
+	seed: u64,
+}
+
+impl JavaRandom {
+	const MULT: u64 = 0x5DEECE66D;
+	const MASK: u64 = (1 << 48) - 1;
+
+	fn new(seed: i64) -> Self {
+		Self { seed: (seed as u64 ^ Self::MULT) & Self::MASK }
+	}
+
+	fn next(&mut self, bits: u32) -> i32 {
+		self.seed = (self.seed.wrapping_mul(Self::MULT).wrapping_add(0xB)) & Self::MASK;
+		(self.seed >> (48 - bits)) as i32
+	}
+
+	fn next_int(&mut self, bound: i32) -> i32 {
+		if bound & -bound == bound {
+			return ((bound as i64 * self.next(31) as i64) >> 31) as i32;
+		}
+		loop {
+			let bits = self.next(31);
+			let val = bits % bound;
+			if bits.wrapping_sub(val).wrapping_add(bound - 1) >= 0 { return val; }
+		}
+	}
+}
+
+// CPython 的 MT19937（random.seed(int) 使用 init_by_array）
+struct Mt19937 {
+	mt: [u32; 624],
+	idx: usize,
+}
+
+impl Mt19937 {
+	fn init_genrand(s: u32) -> Self {
+		let mut mt = [0u32; 624];
+		mt[0] = s;
+		for i in 1..624 {
+			mt[i] = 1812433253u32.wrapping_mul(mt[i - 1] ^ (mt[i - 1] >> 30)).wrapping_add(i as u32);
+		}
+		Self { mt, idx: 624 }
+	}
+
+	fn new_python(seed: u64) -> Self {
+		// 整数种子按 32 位小端分组作为 key
+		let key: Vec<u32> = if seed >> 32 != 0 { vec![seed as u32, (seed >> 32) as u32] } else { vec![seed as u32] };
+		let mut r = Self::init_genrand(19650218);
+		let mt = &mut r.mt;
+		let (mut i, mut j) = (1usize, 0usize);
+		for _ in 0..624.max(key.len()) {
+			mt[i] = (mt[i] ^ (mt[i - 1] ^ (mt[i - 1] >> 30)).wrapping_mul(1664525))
+				.wrapping_add(key[j])
+				.wrapping_add(j as u32);
+			i += 1;
+			j += 1;
+			if i >= 624 { mt[0] = mt[623]; i = 1; }
+			if j >= key.len() { j = 0; }
+		}
+		for _ in 0..623 {
+			mt[i] = (mt[i] ^ (mt[i - 1] ^ (mt[i - 1] >> 30)).wrapping_mul(1566083941)).wrapping_sub(i as u32);
+			i += 1;
+			if i >= 624 { mt[0] = mt[623]; i = 1; }
+		}
+		mt[0] = 0x8000_0000;
+		r
+	}
+
+	fn next_u32(&mut self) -> u32 {
+		if self.idx >= 624 {
+			for k in 0..624 {
+				let y = (self.mt[k] & 0x8000_0000) | (self.mt[(k + 1) % 624] & 0x7fff_ffff);
+				let mut v = self.mt[(k + 397) % 624] ^ (y >> 1);
+				if y & 1 != 0 { v ^= 0x9908_b0df; }
+				self.mt[k] = v;
+			}
+			self.idx = 0;
+		}
+		let mut y = self.mt[self.idx];
+		self.idx += 1;
+		y ^= y >> 11;
+		y ^= (y << 7) & 0x9d2c_5680;
+		y ^= (y << 15) & 0xefc6_0000;
+		y ^= y >> 18;
+		y
+	}
+
+	// random._randbelow_with_getrandbits
+	fn randbelow(&mut self, n: u32) -> u32 {
+		let k = 32 - n.leading_zeros();
+		loop {
+			let r = self.next_u32() >> (32 - k);
+			if r < n { return r; }
+		}
+	}
+}
+
+// glibc random()/rand()，TYPE_3 加性反馈生成器
+struct GlibcRand {
+	r: [u32; 34],
+	i: usize,
+}
+
+impl GlibcRand {
+	fn new(seed: u32) -> Self {
+		let mut r = [0u32; 34];
+		r[0] = if seed == 0 { 1 } else { seed };
+		for i in 1..31 {
+			let prev = r[i - 1] as i32 as i64;
+			let mut word = (16807 * prev) % 2147483647;
+			if word < 0 { word += 2147483647; }
+			r[i] = word as u32;
+		}
+		for i in 31..34 { r[i] = r[i - 31]; }
+		let mut g = Self { r, i: 34 };
+		// 丢弃前 310 个输出
+		for _ in 34..344 { g.step(); }
+		g
+	}
+
+	fn step(&mut self) -> u32 {
+		let v = self.r[(self.i + 3) % 34].wrapping_add(self.r[(self.i + 31) % 34]);
+		self.r[self.i % 34] = v;
+		self.i += 1;
+		v
+	}
+
+	fn next(&mut self) -> u32 {
+		self.step() >> 1
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::opts::{parse_order, OrderSpec};
+
+	fn order(s: &str) -> Vec<usize> {
+		match parse_order(s).unwrap() {
+			OrderSpec::Random { prng, seed } => permutation(prng, seed, 10),
+			o => panic!("{} parsed as {:?}", s, o),
+		}
+	}
+
+	// 期望值分别取自 Collections.shuffle(list, new Random(seed))、CPython 3 random.shuffle、glibc srand/rand
+	#[test]
+	fn matches_reference_shuffles() {
+		assert_eq!(order("rand:java:1234"), [9, 0, 6, 1, 3, 2, 4, 7, 5, 8]);
+		assert_eq!(order("rand:java:-1234"), [3, 4, 2, 5, 7, 0, 9, 6, 1, 8]);
+		assert_eq!(order("rand:python:1234"), [2, 8, 3, 5, 6, 4, 9, 0, 1, 7]);
+		assert_eq!(order("rand:python:-1234"), [2, 8, 3, 5, 6, 4, 9, 0, 1, 7]);
+		assert_eq!(order("rand:python:1099511627783"), [0, 5, 2, 3, 6, 1, 8, 4, 7, 9]);
+		assert_eq!(order("rand:c:1234"), [5, 6, 8, 7, 1, 9, 0, 3, 2, 4]);
+		assert_eq!(order("rand:c:-1234"), [0, 5, 1, 4, 2, 3, 9, 8, 6, 7]);
+	}
+
+	#[test]
+	fn glibc_rand_sequence() {
+		let mut r = GlibcRand::new(1);
+		assert_eq!([r.next(), r.next(), r.next()], [1804289383, 846930886, 1681692777]);
+	}
+}
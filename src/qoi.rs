@@ -0,0 +1,114 @@
+// QOI 解码：像素按规范逐个还原，并定位结束标记以取出其后的数据
+use anyhow::{bail, Result};
+
+const END: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+#[derive(Debug, Clone)]
+pub struct Qoi {
+	pub width: usize,
+	pub height: usize,
+	pub channels: usize,
+	pub rgba: Vec<u8>,
+	pub trailer: Vec<u8>, // 结束标记之后的数据
+}
+
+pub fn parse(data: &[u8]) -> Result<Qoi> {
+	if data.len() < 14 || &data[..4] != b"qoif" {
+		bail!("Not a QOI file");
+	}
+	let width = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+	let height = u32::from_be_bytes([data[8], data[9], data[10], data[11]]) as usize;
+	let channels = data[12] as usize;
+	if width == 0 || height == 0 || width * height > 1 << 26 {
+		bail!("bad QOI size {}x{}", width, height);
+	}
+
+	let count = width * height;
+	let mut rgba = Vec::with_capacity(count * 4);
+	let mut index = [[0u8; 4]; 64];
+	let mut px = [0u8, 0, 0, 255];
+	let mut pos = 14;
+	while rgba.len() < count * 4 {
+		let Some(&b) = data.get(pos) else { break; };
+		pos += 1;
+		let mut run = 1;
+		match b {
+			0xFE => {
+				let Some(c) = data.get(pos..pos + 3) else { break; };
+				px[..3].copy_from_slice(c);
+				pos += 3;
+			}
+			0xFF => {
+				let Some(c) = data.get(pos..pos + 4) else { break; };
+				px.copy_from_slice(c);
+				pos += 4;
+			}
+			_ => match b >> 6 {
+				0 => px = index[b as usize & 63],
+				1 => {
+					px[0] = px[0].wrapping_add((b >> 4) & 3).wrapping_sub(2);
+					px[1] = px[1].wrapping_add((b >> 2) & 3).wrapping_sub(2);
+					px[2] = px[2].wrapping_add(b & 3).wrapping_sub(2);
+				}
+				2 => {
+					let Some(&b2) = data.get(pos) else { break; };
+					pos += 1;
+					let dg = (b & 63).wrapping_sub(32);
+					px[0] = px[0].wrapping_add(dg).wrapping_add(b2 >> 4).wrapping_sub(8);
+					px[1] = px[1].wrapping_add(dg);
+					px[2] = px[2].wrapping_add(dg).wrapping_add(b2 & 15).wrapping_sub(8);
+				}
+				_ => run = (b & 63) as usize + 1,
+			},
+		}
+		let h = (px[0] as usize * 3 + px[1] as usize * 5 + px[2] as usize * 7 + px[3] as usize * 11) % 64;
+		index[h] = px;
+		for _ in 0..run { rgba.extend_from_slice(&px); }
+	}
+	rgba.resize(count * 4, 0);
+
+	let end = if data.get(pos..pos + 8) == Some(&END[..]) { pos + 8 } else { pos };
+	Ok(Qoi { width, height, channels, rgba, trailer: data.get(end..).unwrap_or_default().to_vec() })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decode_ops_and_trailer() {
+		let mut data = b"qoif".to_vec();
+		data.extend_from_slice(&3u32.to_be_bytes());
+		data.extend_from_slice(&2u32.to_be_bytes());
+		data.extend_from_slice(&[4, 0]);
+		data.extend_from_slice(&[
+			0xFE, 10, 20, 30, // RGB
+			0xC0, // RUN 1
+			0x40 | 3 << 4 | 1 << 2 | 2, // DIFF (+1, -1, 0)
+			0x80 | 34, 0x9A, // LUMA dg=+2, dr-dg=+1, db-dg=+2
+			0xFF, 1, 2, 3, 4, // RGBA
+		]);
+		// INDEX：第一个像素 (10,20,30,255) 的哈希
+		data.push(((10 * 3 + 20 * 5 + 30 * 7 + 255 * 11) % 64) as u8);
+		data.extend_from_slice(&END);
+		data.extend_from_slice(b"hidden");
+		let q = parse(&data).unwrap();
+		assert_eq!((q.width, q.height, q.channels), (3, 2, 4));
+		assert_eq!(q.rgba, [
+			10, 20, 30, 255, 10, 20, 30, 255, 11, 19, 30, 255,
+			14, 21, 34, 255, 1, 2, 3, 4, 10, 20, 30, 255,
+		]);
+		assert_eq!(q.trailer, b"hidden");
+	}
+
+	#[test]
+	fn bad_size_and_truncated_data() {
+		let header = |w: u32, h: u32| [&b"qoif"[..], &w.to_be_bytes(), &h.to_be_bytes(), &[3, 0]].concat();
+		assert!(parse(&header(0, 1)).is_err());
+		assert!(parse(&header(1 << 16, 1 << 16)).is_err());
+		// 数据不足时其余像素补 0，没有结束标记
+		let q = parse(&[&header(2, 1)[..], &[0xFE, 1, 2, 3]].concat()).unwrap();
+		assert_eq!(q.rgba, [1, 2, 3, 255, 0, 0, 0, 0]);
+		assert!(q.trailer.is_empty());
+	}
+}
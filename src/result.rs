@@ -24,15 +24,33 @@ fn escape_bytes_to_string(data: &[u8]) -> String {
     result
 }
 
+// 从数据开头开始，或长文本且包含空格、只含常见 ASCII 字符
+fn partial_text_likely(text: &str, offset: usize) -> bool {
+    offset == 0
+        || (text.len() > 10 && text.contains(' ')
+            && text.chars().all(|c| c.is_ascii_alphanumeric() || " .,:!_-\r\n\t".contains(c)))
+}
+
+// file 命令结果匹配常见容器/图像格式的关键词
+fn file_type_likely(desc: &str) -> bool {
+    let keywords = ["bitmap", "jpeg", "pdf", "zip", "rar", "7z", "7-z"];
+    desc.to_lowercase().split_whitespace().any(|word| keywords.iter().any(|kw| word.starts_with(kw)))
+}
+
 #[derive(Debug, Clone)]
 pub enum DetectResult {
     OneChar { ch: u8, size: usize },
     WholeText(String),
     PartialText { text: String, offset: usize },
-    Zlib { data: Vec<u8>, offset: usize, size: usize },
+    Zlib { data: Vec<u8>, offset: usize },
     OpenStego { version: u8, data_len: u32, channel_bits: u8, fname_len: u8, compress: u8, encrypt: u8, fname: String },
     WBStego { size: u32, ext: Option<String>, data_preview: Vec<u8>, enc: Option<String>, even: bool },
     FileType(String),
+    Cracked { password: String, data: Vec<u8> },
+    SteganographyPng { mode: u8, size: u32, name: String, data: Vec<u8> },
+    LsbTool { tool: String, size: Option<usize>, data: Vec<u8> },
+    Steganalysis { tool: String, confidence: f64, detail: String },
+    ChunkAnomaly(String),
 }
 
 impl fmt::Display for DetectResult {
@@ -49,12 +67,7 @@ impl fmt::Display for DetectResult {
             DetectResult::PartialText { text, offset } => {
                 // PartialText: 根据条件决定颜色
                 let text_repr = format!("{:?}", text);
-                let colored_text = if *offset == 0 {
-                    // 从数据开头开始 => 亮红色
-                    text_repr.bright_red().to_string()
-                } else if text.len() > 10 && text.contains(' ') && 
-                          text.chars().all(|c| c.is_ascii_alphanumeric() || " .,:!_-\r\n\t".contains(c)) {
-                    // 长文本且包含空格，且是ASCII字符 => 亮红色
+                let colored_text = if partial_text_likely(text, *offset) {
                     text_repr.bright_red().to_string()
                 } else {
                     // 其他情况不加颜色
@@ -62,7 +75,7 @@ impl fmt::Display for DetectResult {
                 };
                 write!(f, "{}{}", "text: ".bright_black(), colored_text)
             }
-            DetectResult::Zlib { data, offset, size } => {
+            DetectResult::Zlib { data, offset } => {
                 // 限制预览大小为100字节（与原版一致）
                 const MAX_SHOW_SIZE: usize = 100;
                 let preview_data = &data[..data.len().min(MAX_SHOW_SIZE)];
@@ -113,22 +126,86 @@ impl fmt::Display for DetectResult {
                 let colored_desc = if desc.to_lowercase().contains("dbase 3 data") {
                     // DBase 3 data => 整体灰色
                     format!("file: {}", desc).bright_black().to_string()
+                } else if file_type_likely(desc) {
+                    format!("file: {}", desc.bright_red())
                 } else {
-                    // 检查是否匹配特定关键词
-                    let keywords = ["bitmap", "jpeg", "pdf", "zip", "rar", "7z", "7-z"];
-                    let is_highlighted = desc.to_lowercase().split_whitespace()
-                        .any(|word| keywords.iter().any(|kw| word.starts_with(kw)));
-                    
-                    if is_highlighted {
-                        format!("file: {}", desc.bright_red())
-                    } else {
-                        format!("file: {}", desc.yellow())
-                    }
+                    format!("file: {}", desc.yellow())
                 };
                 write!(f, "{}", colored_desc)
             }
+            DetectResult::SteganographyPng { mode, size, name, data } => {
+                const MAX_SHOW_SIZE: usize = 100;
+                let preview = escape_bytes_to_string(&data[..data.len().min(MAX_SHOW_SIZE)]);
+                let suffix = if data.len() > MAX_SHOW_SIZE { "..." } else { "" };
+                // 末行放不下的尾部未被写入图像
+                let lost = if data.len() < *size as usize { format!(" stored={}", data.len()) } else { String::new() };
+                let summary = format!(
+                    "<steganography-png bits={} size={}{} name={:?} data={}{}>",
+                    mode, size, lost, name, preview, suffix
+                );
+                write!(f, "{}", summary.bright_red())
+            }
+            DetectResult::LsbTool { tool, size, data } => {
+                const MAX_SHOW_SIZE: usize = 100;
+                let preview = escape_bytes_to_string(&data[..data.len().min(MAX_SHOW_SIZE)]);
+                let suffix = if data.len() > MAX_SHOW_SIZE { "..." } else { "" };
+                let mut parts = vec![format!("tool={}", tool)];
+                if let Some(n) = size {
+                    parts.push(format!("size={}", n));
+                }
+                parts.push(format!("data={}{}", preview, suffix));
+                write!(f, "{}", format!("<LSB {}>", parts.join(", ")).bright_red())
+            }
+            DetectResult::Steganalysis { tool, confidence, detail } => {
+                // 统计检测，无法提取数据
+                write!(f, "{}", format!("<steganalysis tool={}, confidence={:.2}, {}>", tool, confidence, detail).bright_red())
+            }
+            DetectResult::ChunkAnomaly(desc) => {
+                write!(f, "{}{}", "anomaly: ".bright_black(), desc.bright_red())
+            }
+            DetectResult::Cracked { password, data } => {
+                const MAX_SHOW_SIZE: usize = 100;
+                let preview = escape_bytes_to_string(&data[..data.len().min(MAX_SHOW_SIZE)]);
+                let suffix = if data.len() > MAX_SHOW_SIZE { "..." } else { "" };
+                write!(f, "{}{}{}{}, size={}",
+                    format!("password={:?}", password).bright_red(),
+                    ", data=".bright_black(),
+                    preview,
+                    suffix,
+                    data.len())
+            }
         }
     }
 }
 
 
+
+impl DetectResult {
+    // 可信度评分（0..1），用于批量模式汇总中挑选每个文件最可疑的结果；与显示时的高亮规则一致
+    pub fn score(&self) -> f64 {
+        match self {
+            DetectResult::OneChar { .. } => 0.0,
+            // 文本越长越不像随机噪声，32 字符以上封顶
+            DetectResult::WholeText(text) => 0.5 + 0.3 * text.len().min(32) as f64 / 32.0,
+            DetectResult::PartialText { text, offset } if partial_text_likely(text, *offset) => 0.3 + 0.4 * text.len().min(32) as f64 / 32.0,
+            DetectResult::PartialText { .. } => 0.2,
+            DetectResult::Zlib { .. } => 0.7,
+            DetectResult::OpenStego { .. } => 0.9,
+            DetectResult::WBStego { ext, enc, .. } => if ext.is_some() && enc.is_none() { 0.8 } else { 0.3 },
+            DetectResult::FileType(desc) => {
+                if desc.to_lowercase().contains("dbase 3 data") { 0.1 } else if file_type_likely(desc) { 0.6 } else { 0.3 }
+            }
+            DetectResult::Cracked { .. } => 1.0,
+            DetectResult::SteganographyPng { .. } | DetectResult::LsbTool { .. } => 0.9,
+            DetectResult::Steganalysis { confidence, .. } => *confidence,
+            DetectResult::ChunkAnomaly(_) => 0.4,
+        }
+    }
+}
+
+// 带标题（含子图像前缀）的检测结果
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub title: String,
+    pub result: DetectResult,
+}
@@ -0,0 +1,351 @@
+// JPEG 隐写统计分析：由 DCT 系数直方图估计 F5、OutGuess、steghide 嵌入的可能性，不需要密码。
+// 载体分布用 Fridrich 的校准方法估计：解码亮度平面，裁掉左上各 4 像素后按原量化表重新量化
+use crate::jpeg::{Jpeg, Plane};
+
+// zigzag 序号 -> 自然顺序（v*8+u）
+const ZIGZAG: [usize; 64] = [
+	0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14, 21, 28,
+	35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+// 参与计算的块数（每边）：过小的图像统计不可靠，大图只取左上部分以限制耗时
+const MIN_BLOCKS: usize = 16;
+const MAX_BLOCKS: usize = 128;
+// F5 参考实现（Westfeld）写入的 COM 段
+const F5_COMMENT: &[u8] = b"JPEG Encoder Copyright 1998, James R. Weeks and BioElectronics Research Lab.";
+
+#[derive(Debug, Clone)]
+pub struct Score {
+	pub tool: &'static str,
+	pub confidence: f64,
+	pub detail: String,
+}
+
+pub fn analyze(jpeg: &Jpeg) -> Vec<Score> {
+	let signature = jpeg.segments.iter().any(|s| s.marker == 0xFE && s.data.windows(F5_COMMENT.len()).any(|w| w == F5_COMMENT));
+	let Some(plane) = jpeg.luma.as_ref().filter(|p| p.bw >= MIN_BLOCKS && p.bh >= MIN_BLOCKS) else {
+		return if signature { vec![Score { tool: "F5", confidence: 0.9, detail: "F5 encoder comment".into() }] } else { Vec::new() };
+	};
+	let (bw, bh) = (plane.bw.min(MAX_BLOCKS), plane.bh.min(MAX_BLOCKS));
+
+	let basis = basis();
+	let (pw, ph) = (bw * 8, bh * 8);
+	let pixels = decode(plane, bw, bh, &basis);
+	let observed: Vec<[i16; 64]> = (0..bh).flat_map(|y| (0..bw).map(move |x| (x, y))).map(|(x, y)| plane.blocks[y * plane.bw + x]).collect();
+
+	// 校准：偏移 4 像素后重新分块、变换、量化
+	let (cw, ch) = (bw - 1, bh - 1);
+	let mut calibrated = Vec::with_capacity(cw * ch);
+	for by in 0..ch {
+		for bx in 0..cw {
+			let mut block = [0f64; 64];
+			for (i, v) in block.iter_mut().enumerate() {
+				*v = pixels[(by * 8 + 4 + i / 8) * pw + bx * 8 + 4 + i % 8] - 128.0;
+			}
+			calibrated.push(quantize(&fdct(&block, &basis), &plane.quant));
+		}
+	}
+	let cal_plane = Plane { bw: cw, bh: ch, blocks: calibrated, quant: plane.quant };
+	let cal_pixels = decode(&cal_plane, cw, ch, &basis);
+	// 块效应：块边界处与块中间的像素差之比；校准图像给出同样内容、同样量化下的正常值
+	let b_obs = blockiness(&pixels, pw, ph);
+	let b_cal = blockiness(&cal_pixels, cw * 8, ch * 8);
+	let excess = if b_cal > 0.0 { b_obs / b_cal - 1.0 } else { 0.0 };
+
+	// F5：系数绝对值减 1（收缩）使 0 增多、1 减少；按 (0,1)(1,0)(1,1) 三个频率的直方图分别估计修改率 β，取中位数
+	let scale = observed.len() as f64 / cal_plane.blocks.len() as f64;
+	let mut betas: Vec<f64> = [1, 2, 4].iter().filter_map(|&k| {
+		let h = abs_histogram(&observed, k);
+		let c = abs_histogram(&cal_plane.blocks, k).map(|v| v * scale);
+		let den = c[1] * c[1] + (c[2] - c[1]) * (c[2] - c[1]);
+		(c[1] >= 20.0).then(|| (c[1] * (h[0] - c[0]) + (h[1] - c[1]) * (c[2] - c[1])) / den)
+	}).collect();
+	betas.sort_by(f64::total_cmp);
+	let beta = if betas.len() < 2 { 0.0 } else { betas[betas.len() / 2].max(0.0) };
+	// 纯色背景、文字截图和高质量压缩会让校准图像的 0 偏少，β 因此偏大；
+	// F5 的收缩同时使块效应高于校准图像，两者都满足才报告
+	let shrinkage = ramp(beta, 0.05, 0.25).min(ramp(excess, 0.05, 0.2));
+	let f5 = shrinkage.max(if signature { 0.9 } else { 0.0 });
+	let mut f5_detail = format!("beta={:.3}, blockiness {:+.1}%", beta, excess * 100.0);
+	if signature { f5_detail.push_str(", F5 encoder comment"); }
+
+	// OutGuess / steghide 保持一阶直方图，用 Fridrich 的再嵌入估计已嵌入比例：
+	// OutGuess 只翻转非 0/1 的 AC 系数的 LSB，steghide 对全部非零系数（含 DC）±1 且不变为 0，
+	// 两种再嵌入方式的估计之差区分两者。β 偏大时校准不可靠（或已是 F5），不作估计
+	let reliable = 1.0 - ramp(beta, 0.3, 0.5);
+	let obs_plane = Plane { bw, bh, blocks: observed, quant: plane.quant };
+	let og = reembed(&obs_plane, &cal_plane, &basis, |c, _| (c != 0 && c != 1).then_some(c ^ 1), false);
+	let sh = reembed(&obs_plane, &cal_plane, &basis, |c, r| (c != 0).then_some(if c == 1 || (c != -1 && r & 1 == 0) { c + 1 } else { c - 1 }), true);
+	let outguess = ramp(og, 0.4, 0.6).min(ramp(og - sh, 0.15, 0.3)) * reliable;
+	let steghide = ramp(sh, 0.25, 0.4).min(1.0 - ramp(og - sh, 0.1, 0.2)) * reliable * (1.0 - shrinkage);
+	// OutGuess 0.1 / 未修正的 LSB 替换：非 0/1 系数的值对 (2k,2k+1) 趋于相等
+	let pov = pairs_of_values(&jpeg.coefficients);
+	let detail = format!("blockiness ratio {:.3} vs calibrated {:.3} ({:+.1}%), beta={:.3}", b_obs, b_cal, excess * 100.0, beta);
+
+	vec![
+		Score { tool: "F5", confidence: f5, detail: f5_detail },
+		Score { tool: "OutGuess", confidence: outguess.max(pov), detail: format!("{}, re-embedding p={:.3}, pairs-of-values p={:.3}", detail, og, pov) },
+		Score { tool: "steghide", confidence: steghide, detail: format!("{}, re-embedding p={:.3}", detail, sh) },
+	]
+}
+
+// Fridrich 的再嵌入估计：以最大容量（每个可用系数 1/2 概率）按工具的方式再修改一次，
+// 已含消息的图像块边界差增加得比校准图像少，1 - 两者增量之比即已嵌入的比例
+fn reembed(obs: &Plane, cal: &Plane, basis: &[[f64; 8]; 8], change: impl Fn(i16, u32) -> Option<i16>, dc: bool) -> f64 {
+	let mut x = 0x2545_f491u32;
+	let mut slope = |p: &Plane| {
+		let mut blocks = p.blocks.clone();
+		for b in &mut blocks {
+			for (k, c) in b.iter_mut().enumerate() {
+				if k == 0 && !dc { continue; }
+				x ^= x << 13;
+				x ^= x >> 17;
+				x ^= x << 5;
+				if x & 2 == 0 && let Some(v) = change(*c, x >> 2) { *c = v; }
+			}
+		}
+		let q = Plane { bw: p.bw, bh: p.bh, blocks, quant: p.quant };
+		let edge = |p: &Plane| edge_diff(&decode(p, p.bw, p.bh, basis), p.bw * 8, p.bh * 8, 0);
+		edge(&q) - edge(p)
+	};
+	let (s, c) = (slope(obs), slope(cal));
+	if c > 0.0 { 1.0 - s / c } else { 0.0 }
+}
+
+fn ramp(x: f64, lo: f64, hi: f64) -> f64 {
+	((x - lo) / (hi - lo)).clamp(0.0, 1.0)
+}
+
+// basis[u][x] = C(u)/2 * cos((2x+1)uπ/16)
+fn basis() -> [[f64; 8]; 8] {
+	let mut b = [[0f64; 8]; 8];
+	for (u, row) in b.iter_mut().enumerate() {
+		let c = if u == 0 { std::f64::consts::FRAC_1_SQRT_2 } else { 1.0 };
+		for (x, v) in row.iter_mut().enumerate() {
+			*v = c / 2.0 * ((2 * x + 1) as f64 * u as f64 * std::f64::consts::PI / 16.0).cos();
+		}
+	}
+	b
+}
+
+fn fdct(block: &[f64; 64], b: &[[f64; 8]; 8]) -> [f64; 64] {
+	let mut tmp = [0f64; 64];
+	let mut out = [0f64; 64];
+	for y in 0..8 {
+		for u in 0..8 {
+			tmp[y * 8 + u] = (0..8).map(|x| b[u][x] * block[y * 8 + x]).sum();
+		}
+	}
+	for v in 0..8 {
+		for u in 0..8 {
+			out[v * 8 + u] = (0..8).map(|y| b[v][y] * tmp[y * 8 + u]).sum();
+		}
+	}
+	out
+}
+
+fn idct(coef: &[f64; 64], b: &[[f64; 8]; 8]) -> [f64; 64] {
+	let mut tmp = [0f64; 64];
+	let mut out = [0f64; 64];
+	for v in 0..8 {
+		for x in 0..8 {
+			tmp[v * 8 + x] = (0..8).map(|u| b[u][x] * coef[v * 8 + u]).sum();
+		}
+	}
+	for y in 0..8 {
+		for x in 0..8 {
+			out[y * 8 + x] = (0..8).map(|v| b[v][y] * tmp[v * 8 + x]).sum();
+		}
+	}
+	out
+}
+
+fn quantize(coef: &[f64; 64], quant: &[u16; 64]) -> [i16; 64] {
+	let mut out = [0i16; 64];
+	for (k, v) in out.iter_mut().enumerate() {
+		*v = (coef[ZIGZAG[k]] / quant[k].max(1) as f64).round() as i16;
+	}
+	out
+}
+
+// 反量化、IDCT，得到取整并截断到 0..=255 的像素（与解码器输出一致）
+fn decode(plane: &Plane, bw: usize, bh: usize, b: &[[f64; 8]; 8]) -> Vec<f64> {
+	let pw = bw * 8;
+	let mut pixels = vec![0f64; pw * bh * 8];
+	for by in 0..bh {
+		for bx in 0..bw {
+			let block = &plane.blocks[by * plane.bw + bx];
+			let mut coef = [0f64; 64];
+			for k in 0..64 {
+				coef[ZIGZAG[k]] = block[k] as f64 * plane.quant[k] as f64;
+			}
+			for (i, v) in idct(&coef, b).iter().enumerate() {
+				pixels[(by * 8 + i / 8) * pw + bx * 8 + i % 8] = (v + 128.0).round().clamp(0.0, 255.0);
+			}
+		}
+	}
+	pixels
+}
+
+// 8×8 块边界两侧像素差的平均值，除以块中间（偏移 4）同样位置的平均值
+fn blockiness(pixels: &[f64], w: usize, h: usize) -> f64 {
+	let inner = edge_diff(pixels, w, h, 4);
+	if inner > 0.0 { edge_diff(pixels, w, h, 0) / inner } else { 0.0 }
+}
+
+// 每隔 8 像素（从 offset 起）的列、行两侧像素差的平均值；offset 为 0 时即块边界
+fn edge_diff(pixels: &[f64], w: usize, h: usize, offset: usize) -> f64 {
+	let (mut sum, mut n) = (0.0, 0usize);
+	for y in 0..h {
+		for x in (8 + offset..w).step_by(8) {
+			sum += (pixels[y * w + x - 1] - pixels[y * w + x]).abs();
+			n += 1;
+		}
+	}
+	for y in (8 + offset..h).step_by(8) {
+		for x in 0..w {
+			sum += (pixels[(y - 1) * w + x] - pixels[y * w + x]).abs();
+			n += 1;
+		}
+	}
+	if n == 0 { 0.0 } else { sum / n as f64 }
+}
+
+// 某一频率（zigzag 序号 k）系数绝对值为 0、1、2 的个数
+fn abs_histogram(blocks: &[[i16; 64]], k: usize) -> [f64; 3] {
+	let mut h = [0f64; 3];
+	for b in blocks {
+		if let Some(v) = h.get_mut(b[k].unsigned_abs() as usize) { *v += 1.0; }
+	}
+	h
+}
+
+// Westfeld 的值对卡方检验：对系数序列的前缀逐步计算，返回最大的 p 值
+fn pairs_of_values(coefficients: &[i16]) -> f64 {
+	let used: Vec<i16> = coefficients.iter().copied().filter(|&c| c != 0 && c != 1).collect();
+	let mut best = 0.0f64;
+	for part in 1..=8 {
+		let sample = &used[..used.len() * part / 8];
+		if sample.len() < 1000 { continue; }
+		// LSB 翻转在补码下成对：{2,3}、{4,5}…与 {-1,-2}、{-3,-4}…
+		let mut pairs = std::collections::HashMap::<i16, [f64; 2]>::new();
+		for &c in sample {
+			pairs.entry(c >> 1).or_default()[(c & 1) as usize] += 1.0;
+		}
+		let (mut chi, mut df) = (0.0, 0usize);
+		for [a, b] in pairs.values() {
+			let e = (a + b) / 2.0;
+			if e < 5.0 { continue; }
+			chi += (a - e) * (a - e) / e;
+			df += 1;
+		}
+		if df >= 2 {
+			best = best.max(1.0 - gamma_p((df - 1) as f64 / 2.0, chi / 2.0));
+		}
+	}
+	best
+}
+
+// 正则化下不完全伽马函数 P(a, x)：卡方分布的累积分布函数为 P(df/2, χ²/2)
+fn gamma_p(a: f64, x: f64) -> f64 {
+	if x <= 0.0 { return 0.0; }
+	let ln_front = a * x.ln() - x - ln_gamma(a);
+	if x < a + 1.0 {
+		// 级数展开
+		let (mut sum, mut term, mut n) = (1.0 / a, 1.0 / a, a);
+		for _ in 0..500 {
+			n += 1.0;
+			term *= x / n;
+			sum += term;
+			if term < sum * 1e-12 { break; }
+		}
+		(sum.ln() + ln_front).exp().min(1.0)
+	} else {
+		// 连分式（Lentz 算法）求 Q(a, x)
+		let tiny = 1e-300;
+		let mut b = x + 1.0 - a;
+		let mut c = 1.0 / tiny;
+		let mut d = 1.0 / b;
+		let mut f = d;
+		for i in 1..500 {
+			let an = -(i as f64) * (i as f64 - a);
+			b += 2.0;
+			d = an * d + b;
+			if d.abs() < tiny { d = tiny; }
+			c = b + an / c;
+			if c.abs() < tiny { c = tiny; }
+			d = 1.0 / d;
+			let delta = d * c;
+			f *= delta;
+			if (delta - 1.0).abs() < 1e-12 { break; }
+		}
+		1.0 - (ln_front.exp() * f).min(1.0)
+	}
+}
+
+// Lanczos 近似
+fn ln_gamma(x: f64) -> f64 {
+	const G: [f64; 6] = [76.18009172947146, -86.50532032941677, 24.01409824083091, -1.231739572450155, 0.1208650973866179e-2, -0.5395239384953e-5];
+	let mut y = x;
+	let tmp = x + 5.5;
+	let tmp = tmp - (x + 0.5) * tmp.ln();
+	let mut ser = 1.000000000190015;
+	for g in G {
+		y += 1.0;
+		ser += g / y;
+	}
+	-tmp + (2.5066282746310005 * ser / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn scores(data: &[u8]) -> Vec<Score> {
+		analyze(&crate::jpeg::parse(data).unwrap())
+	}
+
+	fn check(data: &[u8], tool: &str, detected: bool) {
+		let s = scores(data).into_iter().find(|s| s.tool == tool).unwrap();
+		assert_eq!(s.confidence >= 0.5, detected, "{} {:.2} ({})", s.tool, s.confidence, s.detail);
+	}
+
+	// 测试图像由 tests/fixtures/gen_fixtures.py 生成：载体为 samples/flower.png、samples/Code.png，
+	// 隐写样本用该脚本中按 F5、OutGuess、steghide 原实现移植的嵌入算法写入随机消息
+
+	#[test]
+	fn clean_jpegs_are_not_reported() {
+		for data in [&include_bytes!("../tests/fixtures/flower_q90.jpg")[..], include_bytes!("../tests/fixtures/code_q90.jpg")] {
+			for s in scores(data) {
+				assert!(s.confidence < 0.5, "{} {:.2} ({})", s.tool, s.confidence, s.detail);
+			}
+		}
+	}
+
+	#[test]
+	fn f5_is_detected() {
+		let data = include_bytes!("../tests/fixtures/f5_embedded.jpg");
+		check(data, "F5", true);
+		// 去掉编码器注释后仅凭直方图收缩与块效应
+		let mut jpeg = crate::jpeg::parse(data).unwrap();
+		jpeg.segments.retain(|s| s.marker != 0xFE);
+		let f5 = analyze(&jpeg).into_iter().find(|s| s.tool == "F5").unwrap();
+		assert!(f5.confidence >= 0.5, "{:.2} ({})", f5.confidence, f5.detail);
+		assert!(!f5.detail.contains("comment"));
+	}
+
+	#[test]
+	fn outguess_is_detected() {
+		let data = include_bytes!("../tests/fixtures/outguess_embedded.jpg");
+		check(data, "OutGuess", true);
+		check(data, "F5", false);
+		check(data, "steghide", false);
+	}
+
+	#[test]
+	fn steghide_is_detected() {
+		let data = include_bytes!("../tests/fixtures/steghide_embedded.jpg");
+		check(data, "steghide", true);
+		check(data, "F5", false);
+		check(data, "OutGuess", false);
+	}
+}
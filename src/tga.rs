@@ -0,0 +1,184 @@
+// Targa 解析：头部、图像 ID、（RLE 解压后的）像素字节、扩展区、开发者区与文件尾，
+// 以及未被上述结构引用的字节区间
+use anyhow::{bail, Context, Result};
+
+const SIGNATURE: &[u8] = b"TRUEVISION-XFILE.\0";
+
+#[derive(Debug, Clone)]
+pub struct Tga {
+	pub width: usize,
+	pub height: usize,
+	pub imagedata: Vec<u8>, // 按行自上而下排列的原始像素字节（颜色映射图像为索引）
+	pub fields: Vec<(String, Vec<u8>)>, // 图像 ID、扩展区文本、开发者区各字段
+	pub unused: Vec<(usize, Vec<u8>)>,
+}
+
+// 没有魔数，按文件尾签名或头部字段的合法性判断
+pub fn looks_like_tga(data: &[u8]) -> bool {
+	if data.ends_with(SIGNATURE) { return true; }
+	let Some(h) = data.get(..18) else { return false; };
+	let width = u16::from_le_bytes([h[12], h[13]]);
+	let height = u16::from_le_bytes([h[14], h[15]]);
+	h[1] <= 1
+		&& matches!(h[2], 1 | 2 | 3 | 9 | 10 | 11)
+		&& (h[1] == 1) == matches!(h[2], 1 | 9)
+		&& matches!(h[16], 8 | 15 | 16 | 24 | 32)
+		&& width > 0
+		&& height > 0
+		&& h[17] & 0xC0 == 0
+}
+
+pub fn parse(data: &[u8]) -> Result<Tga> {
+	let h = data.get(..18).context("truncated TGA header")?;
+	let rd16 = |p: usize| u16::from_le_bytes([h[p], h[p + 1]]) as usize;
+	let (id_len, image_type) = (h[0] as usize, h[2]);
+	let (width, height, depth) = (rd16(12), rd16(14), h[16] as usize);
+	let cmap_len = if h[1] == 1 { rd16(5) * (h[7] as usize).div_ceil(8) } else { 0 };
+	let bpp = depth.div_ceil(8).max(1);
+	if width == 0 || height == 0 || width * height > 1 << 26 {
+		bail!("bad TGA size {}x{}", width, height);
+	}
+
+	let mut fields = Vec::new();
+	let id = data.get(18..18 + id_len).context("truncated TGA image ID")?;
+	if !id.is_empty() {
+		fields.push(("image ID".to_string(), id.to_vec()));
+	}
+	let start = 18 + id_len + cmap_len;
+	let count = width * height * bpp;
+	let (mut pixels, end) = if image_type >= 9 {
+		unrle(data, start, count, bpp)
+	} else {
+		let end = (start + count).min(data.len());
+		(data.get(start..end).unwrap_or_default().to_vec(), end)
+	};
+	pixels.resize(count, 0);
+	// 描述符第 4 位为 1 表示自右向左存储
+	let line = width * bpp;
+	if h[17] & 0x10 != 0 {
+		for row in pixels.chunks_mut(line) {
+			row.reverse();
+			for px in row.chunks_mut(bpp) { px.reverse(); }
+		}
+	}
+	// 描述符第 5 位为 0 表示自下而上存储
+	let imagedata = if h[17] & 0x20 == 0 {
+		pixels.chunks(line).rev().flatten().copied().collect()
+	} else {
+		pixels
+	};
+
+	let mut used = vec![(0, end)];
+	if data.len() >= 26 && data.ends_with(SIGNATURE) {
+		let foot = data.len() - 26;
+		used.push((foot, data.len()));
+		let rd32 = |p: usize| data.get(p..p + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize).unwrap_or(0);
+		let rd16 = |p: usize| data.get(p..p + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize).unwrap_or(0);
+		let (ext, dev) = (rd32(foot), rd32(foot + 4));
+		// 扩展区：作者、注释、作业名、软件名等文本字段
+		if ext != 0 && let Some(area) = data.get(ext..ext + rd16(ext).max(2)) {
+			used.push((ext, ext + area.len()));
+			for (name, range) in [("author", 2..43), ("comment", 43..367), ("job", 379..420), ("software", 426..467)] {
+				let v = area.get(range).unwrap_or_default();
+				let v = v.iter().rposition(|&b| b != 0 && b != b' ').map(|p| &v[..=p]).unwrap_or_default();
+				if !v.is_empty() { fields.push((name.to_string(), v.to_vec())); }
+			}
+			// 颜色校正表为 256 项 × 4 个 u16，扫描行表为每行一个 u32，
+			// 缩略图为宽、高各 1 字节加未压缩像素
+			let (cc, stamp, scan) = (rd32(ext + 482), rd32(ext + 486), rd32(ext + 490));
+			if area.len() >= 494 {
+				if cc != 0 { used.push((cc, cc + 2048)); }
+				if scan != 0 { used.push((scan, scan + height * 4)); }
+				if stamp != 0 && let Some(d) = data.get(stamp..stamp + 2) {
+					used.push((stamp, stamp + 2 + d[0] as usize * d[1] as usize * bpp));
+				}
+			}
+		}
+		// 开发者区：标签目录，每项 (tag, offset, size)
+		if dev != 0 {
+			let n = rd16(dev);
+			used.push((dev, dev + 2 + n * 10));
+			for i in 0..n {
+				let e = dev + 2 + i * 10;
+				let (tag, off, size) = (rd16(e), rd32(e + 2), rd32(e + 6));
+				let Some(v) = data.get(off..off.saturating_add(size)) else { continue; };
+				used.push((off, off + size));
+				fields.push((format!("developer tag {}", tag), v.to_vec()));
+			}
+		}
+	}
+
+	Ok(Tga { width, height, imagedata, fields, unused: crate::imageio::unused_ranges(data, used) })
+}
+
+// RLE 包：最高位为 1 表示重复一个像素，否则为原样的像素；返回解压数据与包结束位置
+fn unrle(data: &[u8], mut pos: usize, count: usize, bpp: usize) -> (Vec<u8>, usize) {
+	let mut out = Vec::with_capacity(count);
+	while out.len() < count {
+		let Some(&hdr) = data.get(pos) else { break; };
+		pos += 1;
+		let n = (hdr & 0x7f) as usize + 1;
+		if hdr & 0x80 != 0 {
+			let Some(px) = data.get(pos..pos + bpp) else { break; };
+			for _ in 0..n { out.extend_from_slice(px); }
+			pos += bpp;
+		} else {
+			let end = (pos + n * bpp).min(data.len());
+			out.extend_from_slice(&data[pos..end]);
+			pos = end;
+		}
+	}
+	(out, pos)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// 2x1、24 位、未压缩，像素 (1,2,3) (4,5,6)
+	fn tga(descriptor: u8) -> Vec<u8> {
+		let mut out = vec![0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 1, 0, 24, descriptor];
+		out.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+		out
+	}
+
+	fn footer(out: &mut Vec<u8>, ext: usize) {
+		out.extend_from_slice(&(ext as u32).to_le_bytes());
+		out.extend_from_slice(&0u32.to_le_bytes());
+		out.extend_from_slice(SIGNATURE);
+	}
+
+	#[test]
+	fn right_to_left() {
+		assert_eq!(parse(&tga(0x20)).unwrap().imagedata, [1, 2, 3, 4, 5, 6]);
+		assert_eq!(parse(&tga(0x30)).unwrap().imagedata, [4, 5, 6, 1, 2, 3]);
+	}
+
+	#[test]
+	fn extension_tables_are_referenced() {
+		let mut out = tga(0x20);
+		// 缩略图 1x1、扫描行表 1 项、颜色校正表，之后是扩展区
+		let stamp = out.len();
+		out.extend_from_slice(&[1, 1, 7, 8, 9]);
+		let scan = out.len();
+		out.extend_from_slice(&18u32.to_le_bytes());
+		let cc = out.len();
+		out.extend_from_slice(&[0x55; 2048]);
+		let ext = out.len();
+		let mut area = vec![0; 495];
+		area[..2].copy_from_slice(&495u16.to_le_bytes());
+		for (p, v) in [(482, cc), (486, stamp), (490, scan)] {
+			area[p..p + 4].copy_from_slice(&(v as u32).to_le_bytes());
+		}
+		out.extend_from_slice(&area);
+		footer(&mut out, ext);
+		let t = parse(&out).unwrap();
+		assert!(t.unused.is_empty(), "{:?}", t.unused);
+
+		// 表之外的数据仍然报告
+		let mut out = tga(0x20);
+		out.extend_from_slice(b"hidden");
+		footer(&mut out, 0);
+		assert_eq!(parse(&out).unwrap().unused, [(24, b"hidden".to_vec())]);
+	}
+}
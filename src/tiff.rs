@@ -0,0 +1,373 @@
+// TIFF 解析：沿 IFD 链逐页解码条带（无压缩、LZW、PackBits、Deflate），
+// 收集 ASCII 标签，并找出未被任何结构引用的字节区间（条带之间、最后一个 IFD 之后）
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::io::Read;
+
+#[derive(Debug, Clone)]
+pub struct Page {
+	pub width: usize,
+	pub height: usize,
+	pub rgba: Vec<u8>,
+	pub samples: Vec<u8>, // 解压（并还原 predictor）后的条带数据，按存储顺序拼接
+	pub tags: Vec<(String, Vec<u8>)>, // ASCII 标签
+}
+
+#[derive(Debug, Clone)]
+pub struct Tiff {
+	pub pages: Vec<Page>,
+	pub unused: Vec<(usize, Vec<u8>)>, // (偏移, 未引用的字节)
+	pub warnings: Vec<String>, // 无法解码而跳过的 IFD
+}
+
+struct Reader<'a> {
+	data: &'a [u8],
+	le: bool,
+}
+
+impl Reader<'_> {
+	fn u16(&self, p: usize) -> Option<u16> {
+		let b = self.data.get(p..p + 2)?;
+		Some(if self.le { u16::from_le_bytes([b[0], b[1]]) } else { u16::from_be_bytes([b[0], b[1]]) })
+	}
+
+	fn u32(&self, p: usize) -> Option<u32> {
+		let b = self.data.get(p..p + 4)?;
+		let b = [b[0], b[1], b[2], b[3]];
+		Some(if self.le { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+	}
+}
+
+// 一个 IFD 项：类型、个数及值所在的字节区间
+struct Entry {
+	typ: u16,
+	count: usize,
+	offset: usize,
+}
+
+pub fn parse(data: &[u8]) -> Result<Tiff> {
+	let le = match data.get(..4) {
+		Some(b"II*\0") => true,
+		Some(b"MM\0*") => false,
+		Some(b"II+\0" | b"MM\0+") => bail!("BigTIFF is not supported"),
+		_ => bail!("Not a TIFF file"),
+	};
+	let r = Reader { data, le };
+	// 已引用的字节区间
+	let mut used: Vec<(usize, usize)> = vec![(0, 8)];
+	let mut pages = Vec::new();
+	let mut warnings = Vec::new();
+	let mut visited = Vec::new();
+	let mut ifd = r.u32(4).context("truncated TIFF header")? as usize;
+	while ifd != 0 && !visited.contains(&ifd) && visited.len() < 256 {
+		visited.push(ifd);
+		let Some((entries, next)) = read_ifd(&r, ifd, &mut used) else { break; };
+		mark_pointers(&r, &entries, &mut used, &mut visited);
+		match decode_page(&r, &entries) {
+			Ok(page) => pages.push(page),
+			Err(e) => warnings.push(format!("TIFF IFD at 0x{:x}: {:#}", ifd, e)),
+		}
+		ifd = next;
+	}
+	if pages.is_empty() {
+		bail!("no decodable TIFF pages");
+	}
+
+	let unused = crate::imageio::unused_ranges(data, used);
+	Ok(Tiff { pages, unused, warnings })
+}
+
+// 读取一个 IFD 的各项，IFD 本身和放在外部的值记为已引用；返回项与下一个 IFD 的偏移
+fn read_ifd(r: &Reader, ifd: usize, used: &mut Vec<(usize, usize)>) -> Option<(BTreeMap<u16, Entry>, usize)> {
+	let count = r.u16(ifd)? as usize;
+	used.push((ifd, ifd + 6 + count * 12));
+	let mut entries = BTreeMap::new();
+	for i in 0..count {
+		let e = ifd + 2 + i * 12;
+		let (Some(tag), Some(typ), Some(n)) = (r.u16(e), r.u16(e + 2), r.u32(e + 4)) else { break; };
+		let unit = match typ { 1 | 2 | 6 | 7 => 1, 3 | 8 => 2, 4 | 9 | 11 | 13 => 4, 5 | 10 | 12 => 8, _ => continue };
+		let size = unit * n as usize;
+		let offset = if size <= 4 { e + 8 } else { r.u32(e + 8).unwrap_or(0) as usize };
+		if size > 4 { used.push((offset, offset + size)); }
+		entries.insert(tag, Entry { typ, count: n as usize, offset });
+	}
+	Some((entries, r.u32(ifd + 2 + count * 12).unwrap_or(0) as usize))
+}
+
+// 指针标签引用的数据：条带、分块、JPEGInterchangeFormat 缩略图，
+// 以及 SubIFDs、Exif、GPS、Interoperability IFD（递归，与主 IFD 链共用 visited）
+fn mark_pointers(r: &Reader, entries: &BTreeMap<u16, Entry>, used: &mut Vec<(usize, usize)>, visited: &mut Vec<usize>) {
+	let get = |tag: u16| entries.get(&tag).map(|e| values(r, e)).unwrap_or_default();
+	for (off_tag, len_tag) in [(273, 279), (324, 325), (513, 514)] {
+		for (&off, &len) in get(off_tag).iter().zip(&get(len_tag)) {
+			used.push((off as usize, off as usize + len as usize));
+		}
+	}
+	for tag in [330, 34665, 34853, 40965] {
+		for off in get(tag) {
+			let off = off as usize;
+			if off == 0 || visited.contains(&off) || visited.len() >= 256 { continue; }
+			visited.push(off);
+			if let Some((sub, _)) = read_ifd(r, off, used) {
+				mark_pointers(r, &sub, used, visited);
+			}
+		}
+	}
+}
+
+fn values(r: &Reader, e: &Entry) -> Vec<u32> {
+	(0..e.count)
+		.map_while(|i| match e.typ {
+			1 | 7 => r.data.get(e.offset + i).map(|&b| b as u32),
+			3 => r.u16(e.offset + i * 2).map(u32::from),
+			4 | 13 => r.u32(e.offset + i * 4),
+			_ => None,
+		})
+		.collect()
+}
+
+fn decode_page(r: &Reader, entries: &BTreeMap<u16, Entry>) -> Result<Page> {
+	let get = |tag: u16| entries.get(&tag).map(|e| values(r, e)).unwrap_or_default();
+	let first = |tag: u16, default: u32| get(tag).first().copied().unwrap_or(default);
+
+	let tags = entries
+		.iter()
+		.filter(|(_, e)| e.typ == 2)
+		.filter_map(|(&tag, e)| {
+			let v = r.data.get(e.offset..e.offset + e.count)?;
+			let v = v.iter().rposition(|&b| b != 0).map(|p| &v[..=p]).unwrap_or_default();
+			(!v.is_empty()).then(|| (crate::exif::tag_name(tag), v.to_vec()))
+		})
+		.collect();
+
+	let width = first(256, 0) as usize;
+	let height = first(257, 0) as usize;
+	let spp = first(277, 1).max(1) as usize;
+	let bps = first(258, 1) as usize;
+	let compression = first(259, 1);
+	let photometric = first(262, 1);
+	let planar = first(284, 1);
+	let predictor = first(317, 1);
+	if entries.contains_key(&322) {
+		bail!("tiled TIFF is not supported");
+	}
+	if width == 0 || height == 0 || width * height > 1 << 26 {
+		bail!("bad size {}x{}", width, height);
+	}
+	if !matches!(bps, 1 | 2 | 4 | 8 | 16) {
+		bail!("unsupported BitsPerSample {}", bps);
+	}
+	if spp > 16 {
+		bail!("unsupported SamplesPerPixel {}", spp);
+	}
+
+	let offsets = get(273);
+	let counts = get(279);
+	let mut samples = Vec::new();
+	for (&off, &len) in offsets.iter().zip(&counts) {
+		let (off, len) = (off as usize, len as usize);
+		let Some(raw) = r.data.get(off..off.saturating_add(len)) else { continue; };
+		let strip = match compression {
+			1 => raw.to_vec(),
+			5 => weezl::decode::Decoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+				.decode(raw)
+				.unwrap_or_default(),
+			8 | 32946 => {
+				let mut out = Vec::new();
+				let _ = flate2::read::ZlibDecoder::new(raw).read_to_end(&mut out);
+				out
+			}
+			32773 => unpackbits(raw),
+			c => bail!("unsupported compression {}", c),
+		};
+		samples.extend(strip);
+	}
+
+	// 平面配置 2 时每个样本单独成平面
+	let planes = if planar == 2 { spp } else { 1 };
+	let row_spp = spp / planes;
+	let row_bytes = (width * row_spp * bps).div_ceil(8);
+	// 条带不足时补 0，但声明的大小不能远超文件能提供的数据
+	let expected = row_bytes * height * planes;
+	if expected > samples.len() + r.data.len() {
+		bail!("{}x{} image needs {} bytes of strip data, only {} present", width, height, expected, samples.len());
+	}
+	samples.resize(expected, 0);
+	if predictor == 2 {
+		for row in samples.chunks_mut(row_bytes) {
+			undo_predictor(row, row_spp, bps, r.le);
+		}
+	}
+
+	let sample = |x: usize, y: usize, s: usize| -> u32 {
+		if s >= spp { return 0; }
+		let (plane, s, n) = if planes > 1 { (s, 0, 1) } else { (0, s, spp) };
+		let base = (plane * height + y) * row_bytes;
+		let bit = (x * n + s) * bps;
+		match bps {
+			16 => {
+				let p = base + bit / 8;
+				let b = [samples[p], samples[p + 1]];
+				if r.le { u16::from_le_bytes(b) as u32 } else { u16::from_be_bytes(b) as u32 }
+			}
+			_ => ((samples[base + bit / 8] >> (8 - bps - bit % 8)) as u32) & ((1 << bps) - 1),
+		}
+	};
+	// 样本值不缩放以保留低位，16 位取低字节；调色板取颜色值高字节
+	let colormap = get(320);
+	let mut rgba = Vec::with_capacity(width * height * 4);
+	for y in 0..height {
+		for x in 0..width {
+			let px = match (photometric, spp) {
+				(3, _) if !colormap.is_empty() => {
+					let i = sample(x, y, 0) as usize;
+					let n = colormap.len() / 3;
+					let c = |k: usize| (colormap.get(k * n + i).copied().unwrap_or(0) >> 8) as u8;
+					[c(0), c(1), c(2), 0xff]
+				}
+				(_, 1) | (0 | 1, 2) => {
+					let v = sample(x, y, 0) as u8;
+					let a = if spp == 2 { sample(x, y, 1) as u8 } else { 0xff };
+					[v, v, v, a]
+				}
+				(_, 3) => [sample(x, y, 0) as u8, sample(x, y, 1) as u8, sample(x, y, 2) as u8, 0xff],
+				_ => [sample(x, y, 0) as u8, sample(x, y, 1) as u8, sample(x, y, 2) as u8, sample(x, y, 3) as u8],
+			};
+			rgba.extend_from_slice(&px);
+		}
+	}
+
+	Ok(Page { width, height, rgba, samples, tags })
+}
+
+fn unpackbits(data: &[u8]) -> Vec<u8> {
+	let mut out = Vec::new();
+	let mut i = 0;
+	while i < data.len() {
+		let n = data[i] as i8;
+		i += 1;
+		if n >= 0 {
+			let end = (i + n as usize + 1).min(data.len());
+			out.extend_from_slice(&data[i..end]);
+			i = end;
+		} else if n != -128 {
+			if let Some(&b) = data.get(i) {
+				out.extend(std::iter::repeat_n(b, (1 - n as isize) as usize));
+			}
+			i += 1;
+		}
+	}
+	out
+}
+
+// 水平差分还原：每个样本加上同一行前一个像素的对应样本
+fn undo_predictor(row: &mut [u8], spp: usize, bps: usize, le: bool) {
+	match bps {
+		8 => {
+			for i in spp..row.len() {
+				row[i] = row[i].wrapping_add(row[i - spp]);
+			}
+		}
+		16 => {
+			let rd = |b: &[u8], i: usize| if le { u16::from_le_bytes([b[i], b[i + 1]]) } else { u16::from_be_bytes([b[i], b[i + 1]]) };
+			for i in (spp * 2..row.len().saturating_sub(1)).step_by(2) {
+				let v = rd(row, i).wrapping_add(rd(row, i - spp * 2));
+				let b = if le { v.to_le_bytes() } else { v.to_be_bytes() };
+				row[i..i + 2].copy_from_slice(&b);
+			}
+		}
+		_ => {}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// 小端 TIFF：IFD 位于偏移 8，项为 (标签, 类型, 个数, 值或偏移)，tail 紧跟在 IFD 之后
+	fn tiff(entries: &[(u16, u16, u32, u32)], tail: &[u8]) -> Vec<u8> {
+		let mut out = b"II*\0".to_vec();
+		out.extend_from_slice(&8u32.to_le_bytes());
+		out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+		for &(tag, typ, n, v) in entries {
+			out.extend_from_slice(&tag.to_le_bytes());
+			out.extend_from_slice(&typ.to_le_bytes());
+			out.extend_from_slice(&n.to_le_bytes());
+			out.extend_from_slice(&v.to_le_bytes());
+		}
+		out.extend_from_slice(&0u32.to_le_bytes());
+		out.extend_from_slice(tail);
+		out
+	}
+
+	// 2x2 8 位灰度，附加项放在基本标签之后
+	fn gray(extra: &[(u16, u16, u32, u32)], strip: u32) -> Vec<(u16, u16, u32, u32)> {
+		let mut entries = vec![(256, 3, 1, 2), (257, 3, 1, 2), (258, 3, 1, 8), (259, 3, 1, 1), (262, 3, 1, 1), (273, 4, 1, strip), (279, 4, 1, 4)];
+		entries.extend_from_slice(extra);
+		entries
+	}
+
+	#[test]
+	fn pointer_tags_are_referenced() {
+		// tail：条带 4 字节，Exif IFD（一项，外部 ASCII 值 10 字节），JPEG 缩略图 4 字节
+		let tail_at = (8 + 2 + 10 * 12 + 4) as u32;
+		let (exif, thumb) = (tail_at + 4, tail_at + 4 + 18 + 10);
+		let mut tail = vec![1, 2, 3, 4];
+		tail.extend_from_slice(&1u16.to_le_bytes());
+		tail.extend_from_slice(&[0x03, 0x90, 2, 0]);
+		tail.extend_from_slice(&10u32.to_le_bytes());
+		tail.extend_from_slice(&(exif + 18).to_le_bytes());
+		tail.extend_from_slice(&0u32.to_le_bytes());
+		tail.extend_from_slice(b"2024:01:0\0");
+		tail.extend_from_slice(&[0xFF, 0xD8, 0xFF, 0xD9]);
+		let entries = gray(&[(513, 4, 1, thumb), (514, 4, 1, 4), (34665, 4, 1, exif)], tail_at);
+		let t = parse(&tiff(&entries, &tail)).unwrap();
+		assert_eq!(t.pages[0].samples, [1, 2, 3, 4]);
+		assert!(t.unused.is_empty(), "{:?}", t.unused);
+	}
+
+	#[test]
+	fn unreferenced_bytes_are_reported() {
+		let tail_at = (8 + 2 + 7 * 12 + 4) as u32;
+		let t = parse(&tiff(&gray(&[], tail_at), b"\x01\x02\x03\x04hidden")).unwrap();
+		assert_eq!(t.unused, [(tail_at as usize + 4, b"hidden".to_vec())]);
+	}
+
+	#[test]
+	fn undecodable_page_is_a_warning() {
+		// 第二个 IFD 缺少条带偏移：跳过该页并返回警告，而不是直接输出
+		let tail_at = (8 + 2 + 7 * 12 + 4) as u32;
+		let mut data = tiff(&gray(&[], tail_at), &[0; 4]);
+		let next = data.len() as u32;
+		data[tail_at as usize - 4..tail_at as usize].copy_from_slice(&next.to_le_bytes());
+		data.extend_from_slice(&1u16.to_le_bytes());
+		data.extend_from_slice(&[0, 1, 3, 0, 1, 0, 0, 0, 2, 0, 0, 0]);
+		data.extend_from_slice(&0u32.to_le_bytes());
+		let t = parse(&data).unwrap();
+		assert_eq!(t.pages.len(), 1);
+		assert_eq!(t.warnings.len(), 1);
+		assert!(t.warnings[0].starts_with(&format!("TIFF IFD at 0x{:x}: ", next)), "{:?}", t.warnings);
+	}
+
+	#[test]
+	fn exif_ifd_loop() {
+		// Exif IFD 指向主 IFD 自身
+		let tail_at = (8 + 2 + 8 * 12 + 4) as u32;
+		assert!(parse(&tiff(&gray(&[(34665, 4, 1, 8)], tail_at), &[0; 4])).is_ok());
+	}
+
+	#[test]
+	fn too_many_samples_per_pixel() {
+		let tail_at = (8 + 2 + 8 * 12 + 4) as u32;
+		assert!(parse(&tiff(&gray(&[(277, 3, 1, 0xFFFF)], tail_at), &[0; 4])).is_err());
+	}
+
+	#[test]
+	fn size_larger_than_strip_data() {
+		let tail_at = (8 + 2 + 7 * 12 + 4) as u32;
+		let mut entries = gray(&[], tail_at);
+		entries[0].3 = 8192;
+		entries[1].3 = 8192;
+		assert!(parse(&tiff(&entries, &[0; 4])).is_err());
+	}
+}
@@ -0,0 +1,59 @@
+// WebP 的 RIFF 容器：按顺序列出块，并取出 RIFF 声明大小之后的数据
+use anyhow::{bail, Result};
+
+#[derive(Debug, Clone)]
+pub struct Riff {
+	pub chunks: Vec<([u8; 4], Vec<u8>)>,
+	pub trailer: Vec<u8>,
+}
+
+pub fn parse(data: &[u8]) -> Result<Riff> {
+	if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+		bail!("Not a WebP file");
+	}
+	let end = (u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize)
+		.saturating_add(8)
+		.min(data.len());
+	let mut chunks = Vec::new();
+	let mut pos = 12;
+	while pos + 8 <= end {
+		let ty = [data[pos], data[pos + 1], data[pos + 2], data[pos + 3]];
+		let size = u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]]) as usize;
+		let body = &data[pos + 8..(pos + 8).saturating_add(size).min(end)];
+		chunks.push((ty, body.to_vec()));
+		// 块按偶数字节对齐
+		pos = pos + 8 + size + size % 2;
+	}
+	Ok(Riff { chunks, trailer: data[end..].to_vec() })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn chunks_and_trailer() {
+		let mut body = b"WEBP".to_vec();
+		// 奇数长度的块后补 1 字节
+		for (ty, data) in [(b"VP8L", &b"abc"[..]), (b"EXIF", b"Exif\0\0")] {
+			body.extend_from_slice(ty);
+			body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+			body.extend_from_slice(data);
+			if data.len() % 2 == 1 { body.push(0); }
+		}
+		let mut data = b"RIFF".to_vec();
+		data.extend_from_slice(&(body.len() as u32).to_le_bytes());
+		data.extend_from_slice(&body);
+		data.extend_from_slice(b"hidden");
+		let riff = parse(&data).unwrap();
+		assert_eq!(riff.chunks, [(*b"VP8L", b"abc".to_vec()), (*b"EXIF", b"Exif\0\0".to_vec())]);
+		assert_eq!(riff.trailer, b"hidden");
+
+		// 块长度超出 RIFF 声明的大小：截断到 RIFF 末尾
+		data[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+		let riff = parse(&data).unwrap();
+		assert_eq!(riff.chunks, [(*b"VP8L", body[12..].to_vec())]);
+		assert_eq!(riff.trailer, b"hidden");
+		assert!(parse(b"RIFF\0\0\0\0WAVE").is_err());
+	}
+}
@@ -0,0 +1,498 @@
+#!/usr/bin/env python3
+# 生成 src/steganalysis.rs 测试用的 JPEG：python3 tests/fixtures/gen_fixtures.py
+# 只依赖标准库。载体为 samples 中的 PNG，用基线 JPEG 编码器（4:4:4、IJG 量化表、最优 Huffman 表）编码；
+# 隐写样本在量化后的系数上按各工具的嵌入算法修改后再熵编码：
+#   F5       Westfeld F5 v12：SHA1PRNG 置换、31 位状态字、(1,n,k) 矩阵编码与收缩，写入 F5 编码器注释
+#   OutGuess OutGuess 0.2：ARC4 迭代器跳跃选取非 0/1 的 AC 系数做 LSB 替换，再用未使用的系数修正直方图
+#   steghide steghide 0.5：非 0 系数每 3 个一组按奇偶和嵌入，能互换取值的组两两交换，其余 ±1 修改（不变为 0）
+import hashlib
+import math
+import os
+import random
+import struct
+import zlib
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+SAMPLES = os.path.join(HERE, '..', '..', 'samples')
+
+ZIGZAG = [
+	0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14, 21, 28,
+	35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+]
+# ITU-T T.81 附录 K 的示例量化表（自然顺序）
+LUMA_Q = [
+	16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56, 14, 17, 22, 29, 51, 87, 80, 62,
+	18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113, 92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+]
+CHROMA_Q = [
+	17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99, 47, 66, 99, 99, 99, 99, 99, 99,
+] + [99] * 32
+F5_COMMENT = b'JPEG Encoder Copyright 1998, James R. Weeks and BioElectronics Research Lab.'
+
+
+def read_png(path):
+	data = open(path, 'rb').read()
+	pos, idat = 8, b''
+	while pos < len(data):
+		n, = struct.unpack('>I', data[pos:pos + 4])
+		ty, body = data[pos + 4:pos + 8], data[pos + 8:pos + 8 + n]
+		if ty == b'IHDR':
+			w, h, depth, color, _, _, interlace = struct.unpack('>IIBBBBB', body)
+		elif ty == b'IDAT':
+			idat += body
+		pos += 12 + n
+	assert depth == 8 and color in (2, 6) and interlace == 0
+	raw = zlib.decompress(idat)
+	bpp = 3 if color == 2 else 4
+	stride = w * bpp
+	prev = bytearray(stride)
+	pixels = []
+	for y in range(h):
+		f = raw[y * (stride + 1)]
+		line = bytearray(raw[y * (stride + 1) + 1:(y + 1) * (stride + 1)])
+		for i in range(stride):
+			a = line[i - bpp] if i >= bpp else 0
+			b = prev[i]
+			c = prev[i - bpp] if i >= bpp else 0
+			if f == 1:
+				line[i] = (line[i] + a) & 255
+			elif f == 2:
+				line[i] = (line[i] + b) & 255
+			elif f == 3:
+				line[i] = (line[i] + (a + b) // 2) & 255
+			elif f == 4:
+				p = a + b - c
+				pa, pb, pc = abs(p - a), abs(p - b), abs(p - c)
+				line[i] = (line[i] + (a if pa <= pb and pa <= pc else b if pb <= pc else c)) & 255
+		pixels.append([tuple(line[x * bpp:x * bpp + 3]) for x in range(w)])
+		prev = line
+	return w, h, pixels
+
+
+def scaled(table, quality):
+	s = 5000 // quality if quality < 50 else 200 - quality * 2
+	return [min(255, max(1, (q * s + 50) // 100)) for q in table]
+
+
+BASIS = [[(math.sqrt(0.5) if u == 0 else 1.0) / 2 * math.cos((2 * x + 1) * u * math.pi / 16) for x in range(8)] for u in range(8)]
+
+
+def fdct(block):
+	tmp = [sum(BASIS[u][x] * block[y * 8 + x] for x in range(8)) for y in range(8) for u in range(8)]
+	return [sum(BASIS[v][y] * tmp[y * 8 + u] for y in range(8)) for v in range(8) for u in range(8)]
+
+
+# 返回 [分量][块] 的 zigzag 顺序量化系数，块按行优先排列
+def quantized_blocks(w, h, pixels, quality):
+	ycc = [[], [], []]
+	for row in pixels:
+		for c in ycc:
+			c.append([])
+		for r, g, b in row:
+			ycc[0][-1].append(0.299 * r + 0.587 * g + 0.114 * b)
+			ycc[1][-1].append(-0.168736 * r - 0.331264 * g + 0.5 * b + 128)
+			ycc[2][-1].append(0.5 * r - 0.418688 * g - 0.081312 * b + 128)
+	tables = [scaled(LUMA_Q, quality), scaled(CHROMA_Q, quality)]
+	bw, bh = (w + 7) // 8, (h + 7) // 8
+	comps = []
+	for ci, plane in enumerate(ycc):
+		q = tables[min(ci, 1)]
+		blocks = []
+		for by in range(bh):
+			for bx in range(bw):
+				block = [plane[min(by * 8 + i // 8, h - 1)][min(bx * 8 + i % 8, w - 1)] - 128 for i in range(64)]
+				coef = fdct(block)
+				blocks.append([int(round(coef[ZIGZAG[k]] / q[ZIGZAG[k]])) for k in range(64)])
+		comps.append(blocks)
+	return bw, bh, tables, comps
+
+
+def magnitude(v):
+	n = abs(v).bit_length()
+	return n, (v if v >= 0 else v + (1 << n) - 1)
+
+
+# 游程编码后的符号序列：(表, 符号, 附加位数, 附加位)
+def symbols(comps):
+	out = []
+	nblocks = len(comps[0])
+	pred = [0, 0, 0]
+	for i in range(nblocks):
+		for ci in range(3):
+			block = comps[ci][i]
+			cls = min(ci, 1)
+			n, bits = magnitude(block[0] - pred[ci])
+			pred[ci] = block[0]
+			out.append((('dc', cls), n, n, bits))
+			run = 0
+			for k in range(1, 64):
+				if block[k] == 0:
+					run += 1
+					continue
+				while run > 15:
+					out.append((('ac', cls), 0xF0, 0, 0))
+					run -= 16
+				n, bits = magnitude(block[k])
+				out.append((('ac', cls), (run << 4) | n, n, bits))
+				run = 0
+			if run:
+				out.append((('ac', cls), 0x00, 0, 0))
+	return out
+
+
+# T.81 附录 K.2：码长限制为 16 的 Huffman 表
+def huffman_table(freq):
+	freq = dict(freq)
+	freq[256] = 1  # 保留全 1 码字
+	codesize = {s: 0 for s in freq}
+	others = {s: None for s in freq}
+	while True:
+		live = [s for s in freq if freq[s] > 0]
+		if len(live) < 2:
+			break
+		# 频率最小的两个符号，频率相同时取值较大者
+		live.sort(key=lambda s: (freq[s], -s))
+		v1, v2 = live[0], live[1]
+		freq[v1] += freq[v2]
+		freq[v2] = 0
+		codesize[v1] += 1
+		while others[v1] is not None:
+			v1 = others[v1]
+			codesize[v1] += 1
+		others[v1] = v2
+		codesize[v2] += 1
+		while others[v2] is not None:
+			v2 = others[v2]
+			codesize[v2] += 1
+	bits = [0] * 33
+	for s, n in codesize.items():
+		if n:
+			bits[n] += 1
+	i = 32
+	while i > 16:
+		while bits[i] > 0:
+			j = i - 2
+			while bits[j] == 0:
+				j -= 1
+			bits[i] -= 2
+			bits[i - 1] += 1
+			bits[j + 1] += 2
+			bits[j] -= 1
+		i -= 1
+	while bits[i] == 0:
+		i -= 1
+	bits[i] -= 1
+	order = sorted((s for s in codesize if codesize[s] and s != 256), key=lambda s: (codesize[s], s))
+	return bits[1:17], order
+
+
+def codes(bits, vals):
+	table, code, k = {}, 0, 0
+	for n in range(1, 17):
+		for _ in range(bits[n - 1]):
+			table[vals[k]] = (code, n)
+			code += 1
+			k += 1
+		code <<= 1
+	return table
+
+
+def segment(marker, body):
+	return bytes([0xFF, marker]) + struct.pack('>H', len(body) + 2) + body
+
+
+def encode(w, h, tables, comps, comment=None):
+	syms = symbols(comps)
+	freqs = {}
+	for key, sym, _, _ in syms:
+		freqs.setdefault(key, {}).setdefault(sym, 0)
+		freqs[key][sym] += 1
+	huff = {key: huffman_table(f) for key, f in freqs.items()}
+	out = b'\xff\xd8' + segment(0xE0, b'JFIF\x00\x01\x01\x00\x00\x01\x00\x01\x00\x00')
+	if comment:
+		out += segment(0xFE, comment)
+	for i, q in enumerate(tables):
+		out += segment(0xDB, bytes([i]) + bytes(q[ZIGZAG[k]] for k in range(64)))
+	out += segment(0xC0, struct.pack('>BHHB', 8, h, w, 3) + bytes([1, 0x11, 0, 2, 0x11, 1, 3, 0x11, 1]))
+	for (kind, cls), (bits, vals) in sorted(huff.items()):
+		out += segment(0xC4, bytes([(0x10 if kind == 'ac' else 0) | cls]) + bytes(bits) + bytes(vals))
+	out += segment(0xDA, bytes([3, 1, 0x00, 2, 0x11, 3, 0x11, 0, 63, 0]))
+	table = {key: codes(*hv) for key, hv in huff.items()}
+	acc, nacc, data = 0, 0, bytearray()
+	for key, sym, n, extra in syms:
+		code, length = table[key][sym]
+		for value, size in ((code, length), (extra, n)):
+			acc = (acc << size) | value
+			nacc += size
+			while nacc >= 8:
+				byte = (acc >> (nacc - 8)) & 0xFF
+				data.append(byte)
+				if byte == 0xFF:
+					data.append(0)
+				nacc -= 8
+			acc &= (1 << nacc) - 1
+	if nacc:
+		byte = ((acc << (8 - nacc)) | ((1 << (8 - nacc)) - 1)) & 0xFF
+		data.append(byte)
+		if byte == 0xFF:
+			data.append(0)
+	return out + bytes(data) + b'\xff\xd9'
+
+
+# 与编码顺序一致的系数数组（逐 MCU、逐分量、每块 64 个 zigzag 系数）
+def flatten(comps):
+	return [c for i in range(len(comps[0])) for ci in range(3) for c in comps[ci][i]]
+
+
+def unflatten(coeff, comps):
+	for i in range(len(comps[0])):
+		for ci in range(3):
+			base = (i * 3 + ci) * 64
+			comps[ci][i] = coeff[base:base + 64]
+
+
+def java_int(v):
+	v &= 0xFFFFFFFF
+	return v - (1 << 32) if v & 0x80000000 else v
+
+
+# java.security.SecureRandom("SHA1PRNG")，F5Random 的底层
+class Sha1Prng:
+	def __init__(self, seed):
+		self.state = bytearray(hashlib.sha1(seed).digest())
+		self.rem = b''
+
+	def next_byte(self):
+		if not self.rem:
+			out = hashlib.sha1(bytes(self.state)).digest()
+			last, zf = 1, False
+			for i in range(20):
+				v = (self.state[i] ^ 0x80) - 0x80 + (out[i] ^ 0x80) - 0x80 + last
+				zf |= self.state[i] != v & 0xFF
+				self.state[i] = v & 0xFF
+				last = v >> 8
+			if not zf:
+				self.state[0] = (self.state[0] + 1) & 0xFF
+			self.rem = out
+		b, self.rem = self.rem[0], self.rem[1:]
+		return (b ^ 0x80) - 0x80
+
+	def next_value(self, max_value):
+		v = java_int(self.next_byte() | self.next_byte() << 8 | self.next_byte() << 16 | self.next_byte() << 24)
+		v = int(math.fmod(v, max_value))
+		return v + max_value if v < 0 else v
+
+
+def f5_embed(coeff, message, password):
+	count = len(coeff)
+	one = sum(1 for i, c in enumerate(coeff) if i % 64 and c in (1, -1))
+	zero = sum(1 for i, c in enumerate(coeff) if i % 64 and c == 0)
+	large = count - zero - one - count // 64
+	expected = large + int(0.49 * one)
+	k = 1
+	for i in range(1, 8):
+		n = (1 << i) - 1
+		usable = expected * i // n - expected * i // n % n
+		if usable // 8 == 0:
+			break
+		if usable // 8 >= len(message) + 4:
+			k = i
+	n = (1 << k) - 1
+	rnd = Sha1Prng(password)
+	perm = list(range(count))
+	top = count
+	for _ in range(count):
+		j = rnd.next_value(top)
+		top -= 1
+		perm[j], perm[top] = perm[top], perm[j]
+	data = iter(message)
+
+	status = len(message) | k << 24
+	for s in range(4):
+		status ^= (rnd.next_byte() & 0xFF) << (8 * s)
+	byte, avail = status >> 1, 31
+	bit = status & 1
+	pos = 0
+	while pos < count:
+		idx = perm[pos]
+		pos += 1
+		if idx % 64 == 0 or coeff[idx] == 0:
+			continue
+		if coeff[idx] > 0:
+			if coeff[idx] & 1 != bit:
+				coeff[idx] -= 1
+		elif coeff[idx] & 1 == bit:
+			coeff[idx] += 1
+		if coeff[idx] == 0:
+			continue  # 收缩：同一位写入下一个系数
+		if avail == 0:
+			if n > 1:
+				break
+			nxt = next(data, None)
+			if nxt is None:
+				return k
+			byte, avail = nxt ^ rnd.next_byte(), 8
+		bit, byte, avail = byte & 1, byte >> 1, avail - 1
+
+	avail, start = 0, pos
+	while True:
+		kbits, last = 0, False
+		for i in range(k):
+			if avail == 0:
+				nxt = next(data, None)
+				if nxt is None:
+					last = True
+					break
+				byte, avail = nxt ^ rnd.next_byte(), 8
+			kbits |= (byte & 1) << i
+			byte, avail = byte >> 1, avail - 1
+		while True:
+			group, j = [], start
+			while len(group) < n:
+				if j >= count:
+					return k
+				idx = perm[j]
+				j += 1
+				if idx % 64 and coeff[idx] != 0:
+					group.append(idx)
+			h = 0
+			for i, idx in enumerate(group):
+				if (coeff[idx] & 1 if coeff[idx] > 0 else 1 - (coeff[idx] & 1)) == 1:
+					h ^= i + 1
+			change = h ^ kbits
+			if change == 0:
+				break
+			idx = group[change - 1]
+			coeff[idx] += -1 if coeff[idx] > 0 else 1
+			if coeff[idx] != 0:
+				break
+		start = j
+		if last:
+			return k
+
+
+class Arc4:
+	def __init__(self, key):
+		self.s = list(range(256))
+		j = 0
+		for i in range(256):
+			j = (j + self.s[i] + key[i % len(key)]) & 0xFF
+			self.s[i], self.s[j] = self.s[j], self.s[i]
+		self.i = self.j = 0
+
+	def byte(self):
+		self.i = (self.i + 1) & 0xFF
+		self.j = (self.j + self.s[self.i]) & 0xFF
+		self.s[self.i], self.s[self.j] = self.s[self.j], self.s[self.i]
+		return self.s[(self.s[self.i] + self.s[self.j]) & 0xFF]
+
+	def word(self):
+		return self.byte() | self.byte() << 8 | self.byte() << 16 | self.byte() << 24
+
+
+def outguess_embed(coeff, message, key):
+	usable = [i for i, c in enumerate(coeff) if i % 64 and c not in (0, 1)]
+	hist = {}
+	for i in usable:
+		hist[coeff[i]] = hist.get(coeff[i], 0) + 1
+	rc4 = Arc4(key)
+	bits = [(b >> s) & 1 for b in struct.pack('<HI', 0x1234, len(message)) + message for s in range(8)]
+	# 迭代器：平均步长让数据分散到全部可用系数，至少留一半未用的系数做修正；超出时 OutGuess 同样拒绝嵌入
+	step = len(usable) // len(bits)
+	if step < 2:
+		raise ValueError('message too long for OutGuess capacity')
+	pos, used = -1, set()
+	for bit in bits:
+		pos += 1 + rc4.word() % (2 * step - 1)
+		if pos >= len(usable):
+			raise ValueError('message too long for OutGuess capacity')
+		idx = usable[pos]
+		used.add(idx)
+		coeff[idx] = (coeff[idx] & ~1) | bit
+	# 修正：对每个值对，在未使用的系数中反向翻转，恢复一阶直方图
+	now = {}
+	for i in usable:
+		now[coeff[i]] = now.get(coeff[i], 0) + 1
+	spare = [i for i in usable if i not in used]
+	random.Random(key).shuffle(spare)
+	for idx in spare:
+		v = coeff[idx]
+		w = v ^ 1
+		if now.get(v, 0) > hist.get(v, 0) and now.get(w, 0) < hist.get(w, 0) and w not in (0, 1):
+			coeff[idx] = w
+			now[v] -= 1
+			now[w] = now.get(w, 0) + 1
+
+
+def steghide_embed(coeff, message, password):
+	samples = [i for i, c in enumerate(coeff) if c != 0]
+	rnd = random.Random(hashlib.md5(password).digest())
+	rnd.shuffle(samples)
+	bits = [(b >> s) & 1 for b in struct.pack('>I', len(message)) + message for s in range(8)]
+	vertices = [samples[3 * i:3 * i + 3] for i in range(len(bits))]
+	if len(vertices[-1]) < 3:
+		raise ValueError('message too long for steghide capacity')
+
+	def targets(v):
+		return [t for t in (v - 1, v + 1) if t != 0]
+
+	need = [v for v, bit in zip(vertices, bits) if sum(coeff[i] & 1 for i in v) & 1 != bit]
+	# 交换：顶点 u 的某个样本取值 a、顶点 v 的某个样本取值 b，b 是 a 的相邻值且反之亦然，
+	# 两个样本互换取值即可同时翻转两组的奇偶，直方图保持不变
+	by_value = {}
+	for vi, v in enumerate(need):
+		for i in v:
+			by_value.setdefault(coeff[i], []).append((vi, i))
+	done = [False] * len(need)
+	for vi, v in enumerate(need):
+		if done[vi]:
+			continue
+		for i in v:
+			a = coeff[i]
+			partner = None
+			for b in targets(a):
+				for vj, j in by_value.get(b, []):
+					if vj != vi and not done[vj] and a in targets(coeff[j]):
+						partner = (vj, j)
+						break
+				if partner:
+					break
+			if partner:
+				vj, j = partner
+				coeff[i], coeff[j] = coeff[j], coeff[i]
+				done[vi] = done[vj] = True
+				break
+	for vi, v in enumerate(need):
+		if not done[vi]:
+			i = v[rnd.randrange(3)]
+			coeff[i] = rnd.choice(targets(coeff[i]))
+
+
+def write(name, data):
+	with open(os.path.join(HERE, name), 'wb') as f:
+		f.write(data)
+	print(name, len(data))
+
+
+def main():
+	for src, name in (('flower.png', 'flower_q90.jpg'), ('Code.png', 'code_q90.jpg')):
+		w, h, pixels = read_png(os.path.join(SAMPLES, src))
+		bw, bh, tables, comps = quantized_blocks(w, h, pixels, 90)
+		write(name, encode(w, h, tables, comps))
+
+	w, h, pixels = read_png(os.path.join(SAMPLES, 'flower.png'))
+	rnd = random.Random(46)
+	for quality, name, embed, size, comment in (
+		(80, 'f5_embedded.jpg', lambda c, m: f5_embed(c, m, b'abc123'), 1200, F5_COMMENT),
+		(75, 'outguess_embedded.jpg', lambda c, m: outguess_embed(c, m, b'abc123'), 400, None),
+		(90, 'steghide_embedded.jpg', lambda c, m: steghide_embed(c, m, b'abc123'), 900, None),
+	):
+		bw, bh, tables, comps = quantized_blocks(w, h, pixels, quality)
+		coeff = flatten(comps)
+		embed(coeff, bytes(rnd.randrange(256) for _ in range(size)))
+		unflatten(coeff, comps)
+		write(name, encode(w, h, tables, comps, comment))
+
+
+if __name__ == '__main__':
+	main()